@@ -0,0 +1,244 @@
+use crate::{
+    color::Rgb,
+    framework::{Game, Status},
+    game::{
+        ball::Ball,
+        ball_movement::{move_ball_and_score, BallEvent},
+        collision::{Bounds, Collision},
+        input::{handle_player_input, ControlMode, PlayerMotion},
+        level::Level,
+        lives::LivesIndicator,
+        obstacle::{Obstacle, ObstacleDefinition, Shape},
+        player::Player,
+        state,
+    },
+    input::PlayerInput,
+    log,
+    voxelbox::{Draw, Voxelbox},
+};
+use std::{
+    iter,
+    time::{Duration, Instant},
+};
+
+const START_LIVES: u8 = 3;
+/// The size of a brick along the y and z axis, bricks are a single voxel thick
+const BRICK_SIZE: (u8, u8) = (4, 3);
+/// The hit points of the bricks of every layer of a layout, from the wall on the right
+/// towards the player. Every layout adds another layer and makes the layers tougher
+const LAYOUTS: [&[u8]; 3] = [&[1], &[2, 1], &[3, 2, 1]];
+
+/// A brick of the wall, every hit of the ball costs a hit point, it breaks at zero
+struct Brick {
+    obstacle: Obstacle,
+    hit_points: u8,
+}
+
+impl Draw for Brick {
+    fn color(&self) -> Rgb {
+        match self.hit_points {
+            1 => Rgb::cyan(),
+            2 => Rgb::yellow(),
+            3 => Rgb::orange(),
+            _ => Rgb::red(),
+        }
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        self.obstacle.draw()
+    }
+}
+
+/// The bricks of a layout, the layers stand in every second plane from the right wall on
+fn bricks(layout: &[u8], bounds: &Bounds) -> Vec<Brick> {
+    let (height, depth) = BRICK_SIZE;
+    let mut bricks = Vec::new();
+    for (layer, &hit_points) in layout.iter().enumerate() {
+        let x = bounds.width - 1 - 2 * layer as u8;
+        for y in (0..bounds.height).step_by(height.into()) {
+            for z in (0..bounds.depth).step_by(depth.into()) {
+                let to = (
+                    x,
+                    (y + height - 1).min(bounds.height - 1),
+                    (z + depth - 1).min(bounds.depth - 1),
+                );
+                let definition = ObstacleDefinition {
+                    shape: Shape::Block {
+                        from: (x, y, z),
+                        to,
+                    },
+                    motion: None,
+                    color: None,
+                };
+                bricks.push(Brick {
+                    obstacle: Obstacle::new(definition, *bounds),
+                    hit_points,
+                });
+            }
+        }
+    }
+    bricks
+}
+
+/// Player 1 faces a wall of bricks on the right side of the arena, clearing every brick
+/// advances to the next layout. Missing the ball costs a life, the obstacles of the level stay
+pub struct Breakout {
+    level: Level,
+    player: Player,
+    motion: PlayerMotion,
+    ball: Ball,
+    last_move: Instant,
+    bricks: Vec<Brick>,
+    obstacles: Vec<Obstacle>,
+    layout: usize,
+    lives: u8,
+    now: Instant,
+}
+
+impl Breakout {
+    pub fn new(level: Level, control: ControlMode) -> Self {
+        let now = Instant::now();
+        Self {
+            player: level.player(state::Player::Player1),
+            motion: PlayerMotion::new(control),
+            ball: level.ball(),
+            last_move: now,
+            bricks: bricks(LAYOUTS[0], level.bounds()),
+            obstacles: level.obstacles(),
+            layout: 0,
+            lives: START_LIVES,
+            now,
+            level,
+        }
+    }
+
+    /// Takes a hit point from every brick the ball is about to hit, returns the axes
+    /// the ball bounces on. Adjacent bricks hit at once bounce the ball only once
+    fn hit_bricks(&mut self) -> (bool, bool, bool) {
+        let mut bounces = (false, false, false);
+        for brick in &mut self.bricks {
+            let (x, y, z) = self.ball.collides(&brick.obstacle);
+            if x || y || z {
+                brick.hit_points -= 1;
+                bounces = (bounces.0 || x, bounces.1 || y, bounces.2 || z);
+            }
+        }
+        self.bricks.retain(|brick| brick.hit_points > 0);
+        bounces
+    }
+
+    /// Serves a new ball and centers the paddle
+    fn reset(&mut self) {
+        self.player = self.level.player(state::Player::Player1);
+        self.ball = self.level.ball();
+        self.last_move = self.now;
+    }
+}
+
+impl Game for Breakout {
+    fn update(&mut self, dt: Duration, inputs: &mut [PlayerInput]) -> Status {
+        self.now += dt;
+        let now = self.now;
+
+        if let Some(input) = inputs.first() {
+            handle_player_input(input, &mut self.player, &mut self.motion, now);
+        }
+        self.player.update_effects(now);
+        for obstacle in &mut self.obstacles {
+            obstacle.update(now);
+        }
+        if now.duration_since(self.last_move) < self.ball.intervall() {
+            return Status::Running;
+        }
+        self.last_move = now;
+
+        let bounces = self.hit_bricks();
+        if bounces != (false, false, false) {
+            self.ball.change_direction(bounces);
+            self.ball.handle_collision();
+        }
+        let players = std::slice::from_ref(&self.player);
+        if let Some(BallEvent::Conceded(_)) =
+            move_ball_and_score(&mut self.ball, players, iter::empty(), &self.obstacles)
+        {
+            self.lives -= 1;
+            if self.lives == 0 {
+                log!(
+                    Success,
+                    "Game over in layout {} with {} bricks left",
+                    self.layout + 1,
+                    self.bricks.len()
+                );
+                return Status::Finished;
+            }
+            log!(Log, "Missed the ball, {} lives left", self.lives);
+            self.reset();
+        }
+
+        if self.bricks.is_empty() {
+            self.layout += 1;
+            let Some(layout) = LAYOUTS.get(self.layout) else {
+                log!(Success, "Every layout is cleared");
+                return Status::Finished;
+            };
+            log!(Log, "Layout {} cleared", self.layout);
+            self.bricks = bricks(layout, self.level.bounds());
+            self.reset();
+        }
+        Status::Running
+    }
+
+    fn render(&self, voxelbox: &mut Voxelbox) {
+        for obstacle in &self.obstacles {
+            voxelbox.draw(obstacle);
+        }
+        for brick in &self.bricks {
+            voxelbox.draw(brick);
+        }
+        voxelbox.draw(&LivesIndicator::new(&self.player, self.lives));
+        voxelbox.draw(&self.player);
+        voxelbox.draw(&self.ball);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZero;
+
+    #[test]
+    fn test_breakout() {
+        let level = Level::default().with_players(Some(1)).unwrap();
+        let mut breakout = Breakout::new(level, ControlMode::Velocity);
+        // 5 rows and 4 columns of bricks
+        assert_eq!(breakout.bricks.len(), 20);
+        assert_eq!(bricks(LAYOUTS[2], &Bounds::default()).len(), 60);
+
+        // The ball in the corner hits a single brick and breaks it
+        let mut ball = Ball::default().with_direction((NonZero::new(1).unwrap(), 0, 0));
+        ball.position = (17, 1, 1);
+        breakout.ball = ball;
+        assert_eq!(breakout.hit_bricks(), (true, false, false));
+        assert_eq!(breakout.bricks.len(), 19);
+
+        // Between two bricks both break, the tougher layer behind them is untouched
+        breakout.bricks = bricks(LAYOUTS[1], &Bounds::default());
+        breakout.ball.position = (15, 4, 1);
+        assert_eq!(breakout.hit_bricks(), (true, false, false));
+        assert_eq!(breakout.bricks.len(), 38);
+        let weak = breakout
+            .bricks
+            .iter()
+            .filter(|brick| brick.color() == Rgb::cyan());
+        assert_eq!(weak.count(), 18);
+
+        // Clearing the wall advances to the next layout
+        breakout.bricks.clear();
+        assert_eq!(
+            breakout.update(Duration::from_secs(1), &mut []),
+            Status::Running
+        );
+        assert_eq!(breakout.layout, 1);
+        assert_eq!(breakout.bricks.len(), 40);
+    }
+}
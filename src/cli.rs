@@ -1,14 +1,43 @@
-use std::num::NonZero;
+use std::{num::NonZero, path::PathBuf, time::Duration};
 
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 
-use crate::positive::Positive;
+use pong::{
+    game::{input::ControlMode, level::TeamLayout, paddle::PaddleShape},
+    input::Difficulty,
+    positive::Positive,
+};
 
 const DEFAULT_SENSITIVITY: f32 = 1.3;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameKind {
+    #[default]
+    Pong,
+    Snake,
+    Breakout,
+}
+
+impl std::str::FromStr for GameKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pong" => Ok(Self::Pong),
+            "snake" => Ok(Self::Snake),
+            "breakout" => Ok(Self::Breakout),
+            _ => Err(format!("{s} isn't a game, use pong, snake or breakout")),
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(version, about = "3d Pong on the Voxelbox", long_about = None)]
 pub struct Args {
+    /// The game to play: pong, snake (steered by player 1 through the whole cube)
+    /// or breakout (player 1 against a wall of bricks)
+    #[arg(long, default_value = "pong")]
+    pub game: GameKind,
     /// Sensitivity of Player 1 (Green), controls paddle speed
     #[arg(
         long,
@@ -25,6 +54,70 @@ pub struct Args {
     )]
     /// Sensitivity of Player 2 (Yellow), controls paddle speed
     pub sensitivity_p2: Positive<f32>,
+    /// Sensitivity of Player 3 (Cyan), controls paddle speed
+    #[arg(
+        long,
+        visible_alias = "sens-p3",
+        default_value_t = Positive::new(DEFAULT_SENSITIVITY).unwrap(),
+        value_parser = sensitivity_parser
+    )]
+    pub sensitivity_p3: Positive<f32>,
+    /// Sensitivity of Player 4 (Purple), controls paddle speed
+    #[arg(
+        long,
+        visible_alias = "sens-p4",
+        default_value_t = Positive::new(DEFAULT_SENSITIVITY).unwrap(),
+        value_parser = sensitivity_parser
+    )]
+    pub sensitivity_p4: Positive<f32>,
+    /// Control mode of Player 1: velocity (the stick sets the paddle speed) or absolute
+    /// (the stick position is the paddle position)
+    #[arg(long, visible_alias = "control-p1", default_value = "velocity")]
+    pub control_mode_p1: ControlMode,
+    /// Control mode of Player 2: velocity or absolute
+    #[arg(long, visible_alias = "control-p2", default_value = "velocity")]
+    pub control_mode_p2: ControlMode,
+    /// Control mode of Player 3: velocity or absolute
+    #[arg(long, visible_alias = "control-p3", default_value = "velocity")]
+    pub control_mode_p3: ControlMode,
+    /// Control mode of Player 4: velocity or absolute
+    #[arg(long, visible_alias = "control-p4", default_value = "velocity")]
+    pub control_mode_p4: ControlMode,
+    /// Player 1 is controlled by the computer, with the difficulty easy, medium or hard
+    #[arg(long, num_args = 0..=1, default_missing_value = "medium")]
+    pub bot_p1: Option<Difficulty>,
+    /// Player 2 is controlled by the computer
+    #[arg(long, num_args = 0..=1, default_missing_value = "medium")]
+    pub bot_p2: Option<Difficulty>,
+    /// Player 3 is controlled by the computer
+    #[arg(long, num_args = 0..=1, default_missing_value = "medium")]
+    pub bot_p3: Option<Difficulty>,
+    /// Player 4 is controlled by the computer
+    #[arg(long, num_args = 0..=1, default_missing_value = "medium")]
+    pub bot_p4: Option<Difficulty>,
+    /// The number of players, 2 or 4 with players 3 and 4 on the top and bottom wall.
+    /// A single player practices against the opposite wall.
+    /// Overrides the number of players of the level
+    #[arg(long)]
+    pub players: Option<u8>,
+    /// Play 2 vs 2, players 1 and 3 against players 2 and 4 (side-by-side or goalie)
+    #[arg(long)]
+    pub teams: Option<TeamLayout>,
+    /// Paddle shape of Player 1 (diamond, square, circle or cross)
+    #[arg(long, visible_alias = "shape-p1")]
+    pub paddle_shape_p1: Option<PaddleShape>,
+    /// Paddle size of Player 1, must be odd
+    #[arg(long, visible_alias = "size-p1")]
+    pub paddle_size_p1: Option<u8>,
+    /// Paddle shape of Player 2 (diamond, square, circle or cross)
+    #[arg(long, visible_alias = "shape-p2")]
+    pub paddle_shape_p2: Option<PaddleShape>,
+    /// Paddle size of Player 2, must be odd
+    #[arg(long, visible_alias = "size-p2")]
+    pub paddle_size_p2: Option<u8>,
+    /// How many voxels the paddles can be pushed into the arena with the triggers
+    #[arg(long)]
+    pub paddle_depth: Option<u8>,
     /// The number of points needed to win
     #[arg(
         long,
@@ -33,6 +126,82 @@ pub struct Args {
         default_value_t = NonZero::new(5).unwrap()
     )]
     pub winning_points: NonZero<u8>,
+    /// A set is only won with a lead of two points
+    #[arg(long, visible_alias = "deuce")]
+    pub win_by_two: bool,
+    /// With --win-by-two: whoever reaches this many points wins the set regardless of the lead
+    #[arg(long, requires = "win_by_two")]
+    pub point_cap: Option<NonZero<u8>>,
+    /// Play a best-of match over this many sets, each set is played up to the winning points.
+    /// Must be odd so that a match can't end in a tie of sets
+    #[arg(
+        long,
+        visible_alias = "best-of",
+        default_value_t = NonZero::<u8>::MIN,
+        value_parser = sets_parser
+    )]
+    pub sets: NonZero<u8>,
+    /// Play with lives instead of points, the last player (or team) with lives left wins
+    #[arg(long, conflicts_with_all = ["winning_points", "win_by_two", "sets"])]
+    pub lives: Option<NonZero<u8>>,
+    /// Play for this many seconds instead of up to the winning points, the player (or team)
+    /// with the most points wins, a tie goes into sudden death
+    #[arg(
+        long,
+        visible_alias = "time",
+        value_parser = seconds_parser,
+        conflicts_with_all = ["winning_points", "win_by_two", "sets", "lives"]
+    )]
+    pub time_limit: Option<Duration>,
+    /// Multiball: spawn another ball after this many paddle hits
+    #[arg(long, visible_alias = "mb-hits")]
+    pub multiball_hits: Option<NonZero<u8>>,
+    /// Multiball: spawn another ball after this many seconds
+    #[arg(long, visible_alias = "mb-interval", value_parser = seconds_parser)]
+    pub multiball_interval: Option<Duration>,
+    /// Multiball: the maximum number of balls in the game at once
+    #[arg(long, default_value_t = NonZero::new(4).unwrap())]
+    pub max_balls: NonZero<u8>,
+    /// Power-ups: spawn a power-up after this many seconds, power-ups are disabled without it
+    #[arg(long, visible_alias = "pu-interval", value_parser = seconds_parser)]
+    pub powerup_interval: Option<Duration>,
+    /// Power-ups: the number of seconds the effect of a power-up lasts
+    #[arg(
+        long,
+        visible_alias = "pu-duration",
+        default_value = "8",
+        value_parser = seconds_parser
+    )]
+    pub powerup_duration: Duration,
+    /// Simulate this many matches between bots without a gamepad or Voxelbox and print
+    /// statistics, the bots use the difficulties of --bot-p1 to --bot-p4 (medium by default)
+    #[arg(long)]
+    pub simulate: Option<NonZero<u32>>,
+    /// With --simulate: print a CSV line per match instead of a summary
+    #[arg(long, requires = "simulate")]
+    pub csv: bool,
+    /// With --simulate: the seed of the first match, the same seed replays the same matches
+    #[arg(long, requires = "simulate")]
+    pub seed: Option<u64>,
+    /// Play on the keyboard of the terminal instead of gamepads: the first player with WASD,
+    /// the second one with the arrow keys
+    #[arg(long, visible_alias = "kb")]
+    pub keyboard: bool,
+    /// Players control their paddle from another device, e.g. a phone, by sending their stick
+    /// over UDP to this port
+    #[arg(
+        long,
+        conflicts_with_all = ["keyboard", "menu"],
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub network: Option<u16>,
+    /// Pick the mode (classic, multiball, four players or practice) and the winning points on
+    /// the cube before every match, players without a gamepad are played by bots
+    #[arg(long, conflicts_with = "simulate")]
+    pub menu: bool,
+    /// Path to a level file (TOML) describing the arena, paddles, ball and obstacles
+    #[arg(long)]
+    pub level: Option<PathBuf>,
     /// IP-Address of the Voxelbox
     #[arg(
         long,
@@ -48,8 +217,52 @@ pub struct Args {
     pub port: u16,
 }
 
+impl Args {
+    /// Parses the arguments and checks the combinations clap can't express on its own
+    pub fn parse_checked() -> Self {
+        let args = Self::parse();
+        if args.point_cap.is_some_and(|cap| cap < args.winning_points) {
+            Self::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "the point cap can't be below the {} winning points",
+                        args.winning_points
+                    ),
+                )
+                .exit();
+        }
+        args
+    }
+}
+
 fn sensitivity_parser(s: &str) -> Result<Positive<f32>, String> {
     s.parse()
         .map_err(|_| format!("{s} isn't a number"))
         .and_then(|n| Positive::new(n).ok_or_else(|| format!("{s} is bigger than 0")))
 }
+
+fn sets_parser(s: &str) -> Result<NonZero<u8>, String> {
+    s.parse::<NonZero<u8>>()
+        .map_err(|_| format!("{s} isn't a number of sets"))
+        .and_then(|sets| {
+            if sets.get() % 2 == 1 {
+                Ok(sets)
+            } else {
+                Err(format!("{s} sets could end in a tie, use an odd number"))
+            }
+        })
+}
+
+fn seconds_parser(s: &str) -> Result<Duration, String> {
+    s.parse()
+        .map_err(|_| format!("{s} isn't a number"))
+        .and_then(|n| Duration::try_from_secs_f32(n).map_err(|_| format!("{s} isn't a duration")))
+        .and_then(|d| {
+            if d.is_zero() {
+                Err(format!("{s} must be bigger than 0"))
+            } else {
+                Ok(d)
+            }
+        })
+}
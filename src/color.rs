@@ -1,4 +1,7 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+use serde::Deserialize;
+
+/// Deserialized from `[red, green, blue]`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
 pub struct Rgb(u8, u8, u8);
 
 macro_rules! colors_by_name {
@@ -18,13 +21,31 @@ impl Rgb {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Self(r, g, b)
     }
+
+    /// Blends `amount` (from `0.0` to `1.0`) of `other` into the color
+    pub fn mix(self, other: Self, amount: f32) -> Self {
+        let mix = |own: u8, other: u8| (own as f32 + (other as f32 - own as f32) * amount) as u8;
+        Self(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
 }
 
 colors_by_name! {
     black => (0, 0, 0),
     green => (22, 163, 74),
     yellow => (234, 179, 8),
-    pink => (219, 39, 119)
+    pink => (219, 39, 119),
+    cyan => (6, 182, 212),
+    orange => (234, 88, 12),
+    purple => (147, 51, 234),
+    white => (255, 255, 255),
+    gray => (82, 82, 91),
+    red => (220, 38, 38),
+    blue => (37, 99, 235),
+    lime => (132, 204, 22),
 }
 
 impl From<Rgb> for (u8, u8, u8) {
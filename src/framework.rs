@@ -0,0 +1,57 @@
+use crate::{input::PlayerInput, log::Severity, prelude::*, voxelbox::Voxelbox};
+use gilrs::Gilrs;
+use std::time::{Duration, Instant};
+
+/// How often a frame is sent to the Voxelbox
+pub const FPS: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    Finished,
+}
+
+/// A game on the cube, the framework polls the inputs, keeps the time and sends the frames
+pub trait Game {
+    /// Advances the game by `dt`, every gamepad event was already passed to the inputs
+    fn update(&mut self, dt: Duration, inputs: &mut [PlayerInput]) -> Status;
+    /// Draws the current state, the cube is cleared before and sent afterwards
+    fn render(&self, voxelbox: &mut Voxelbox);
+}
+
+/// Runs `game` until it is finished, updating it as often as possible and rendering it
+/// with the frame rate of the cube
+pub fn run(
+    game: &mut impl Game,
+    inputs: &mut [PlayerInput],
+    gilrs: &mut Gilrs,
+    voxelbox: &mut Voxelbox,
+) {
+    let frame_duration = Duration::from_secs_f32(1.0 / FPS);
+    let mut last_update = Instant::now();
+    let mut last_render = last_update;
+
+    loop {
+        while let Some(event) = gilrs.next_event() {
+            for input in inputs.iter_mut() {
+                input.add_event(&event);
+            }
+        }
+
+        let now = Instant::now();
+        let status = game.update(now - last_update, inputs);
+        last_update = now;
+        if status == Status::Finished {
+            return;
+        }
+
+        if now - last_render >= frame_duration {
+            voxelbox.reset_leds();
+            game.render(voxelbox);
+            voxelbox
+                .send()
+                .log(Severity::Warning, "Could not send pixel-data to Voxelbox");
+            last_render = now;
+        }
+    }
+}
@@ -1,59 +1,195 @@
 use rand::Rng;
-use std::{num::NonZero, time::Duration};
-
-use crate::{
-    color::Rgb,
-    odd::Odd,
-    voxelbox::{self, Draw},
+use serde::Deserialize;
+use std::{
+    num::NonZero,
+    time::{Duration, Instant},
 };
 
-const COLLISIONS_UNTIL_SPEED_INC: u8 = 2;
-const MAX_BALL_SLEEP_TIME: f64 = 600.0;
-const MIN_BALL_SLEEP_TIME: f64 = 300.0;
+use super::{collision::Bounds, effect::TimedEffects, player::PlayerSite, state};
+use crate::{color::Rgb, odd::Odd, random, voxelbox::Draw};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BallEffect {
+    SlowMotion,
+    Invisible,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedProfile {
+    /// Milliseconds between two steps of a freshly spawned ball
+    pub start_ms: u64,
+    /// Milliseconds between two steps the ball speeds up to
+    pub fastest_ms: u64,
+    /// Collisions needed until the ball gets faster
+    pub collisions_until_speed_up: NonZero<u8>,
+}
+
+impl Default for SpeedProfile {
+    fn default() -> Self {
+        Self {
+            start_ms: 600,
+            fastest_ms: 300,
+            collisions_until_speed_up: NonZero::new(2).unwrap(),
+        }
+    }
+}
 
+#[derive(Clone)]
 pub struct Ball {
     pub position: (u8, u8, u8),
     color: Rgb,
     direction: (NonZero<i8>, i8, i8),
     collisions_since_speed_inc: u8,
-    pub movement_intervall: Duration,
+    movement_intervall: Duration,
+    bounds: Bounds,
+    speed: SpeedProfile,
+    last_hit: Option<state::Player>,
+    effects: TimedEffects<BallEffect>,
 }
 
 impl Ball {
     const SIZE: Odd<u8> = Odd::<u8>::new_panics(3);
     pub const PADDING: u8 = (Self::SIZE.value() - 1) / 2;
 
-    pub fn new_with_x(x: NonZero<i8>) -> Self {
+    pub fn new(position: (u8, u8, u8), color: Rgb, bounds: Bounds, speed: SpeedProfile) -> Self {
         Self {
-            direction: (x, 0, 0),
-            ..Self::default()
+            position,
+            color,
+            direction: (NonZero::new(1).unwrap(), 0, 0),
+            collisions_since_speed_inc: 0,
+            movement_intervall: Duration::from_millis(speed.start_ms),
+            bounds,
+            speed,
+            last_hit: None,
+            effects: TimedEffects::default(),
         }
     }
 
-    pub fn apply_movement(&mut self) {
+    /// Serves the ball straight to the wall of a player
+    pub fn served_to(mut self, site: &PlayerSite) -> Self {
+        let towards = site.wall_direction();
+        self.direction = match site {
+            PlayerSite::Left | PlayerSite::Right => (NonZero::new(towards).unwrap(), 0, 0),
+            PlayerSite::Top | PlayerSite::Bottom => (self.direction.0, towards, 0),
+        };
+        self
+    }
+
+    pub fn with_color(mut self, color: Rgb) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Gives the ball a random direction and speed, used for multiball
+    pub fn randomized(mut self) -> Self {
+        random::with_rng(|rng| {
+            let x = if rng.random_bool(0.5) { 1 } else { -1 };
+            let ms = rng.random_range(self.speed.fastest_ms..=self.speed.start_ms);
+
+            self.direction = (
+                NonZero::new(x).unwrap(),
+                rng.random_range(-1..=1),
+                rng.random_range(-1..=1),
+            );
+            self.movement_intervall = Duration::from_millis(ms);
+        });
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_direction(mut self, direction: (NonZero<i8>, i8, i8)) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub const fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// Whether the voxel is inside of the ball
+    pub fn covers(&self, (x, y, z): (u8, u8, u8)) -> bool {
+        let (bx, by, bz) = self.position;
+        [(bx, x), (by, y), (bz, z)]
+            .iter()
+            .all(|&(ball, voxel)| ball.abs_diff(voxel) <= Self::PADDING)
+    }
+
+    /// The player who hit the ball the last time
+    pub const fn last_hit(&self) -> Option<state::Player> {
+        self.last_hit
+    }
+
+    pub fn hit_by(&mut self, player: state::Player) {
+        self.last_hit = Some(player);
+    }
+
+    pub fn add_effect(&mut self, effect: BallEffect, until: Instant) {
+        self.effects.add(effect, until);
+    }
+
+    pub fn update_effects(&mut self, now: Instant) {
+        self.effects.update(now);
+    }
+
+    /// The time between two steps, slow motion doubles it
+    pub fn intervall(&self) -> Duration {
+        if self.effects.contains(&BallEffect::SlowMotion) {
+            self.movement_intervall * 2
+        } else {
+            self.movement_intervall
+        }
+    }
+
+    pub const fn direction(&self) -> (i8, i8, i8) {
+        (self.direction.0.get(), self.direction.1, self.direction.2)
+    }
+
+    pub fn next_position(&self) -> (u8, u8, u8) {
         let x = (((self.position.0 as i8) + self.direction.0.get()) as u8)
-            .clamp(Self::PADDING, voxelbox::WIDTH - 1 - Self::PADDING);
+            .clamp(Self::PADDING, self.bounds.width - 1 - Self::PADDING);
         let y = (((self.position.1 as i8) + self.direction.1) as u8)
-            .clamp(Self::PADDING, voxelbox::HEIGHT - 1 - Self::PADDING);
+            .clamp(Self::PADDING, self.bounds.height - 1 - Self::PADDING);
         let z = (((self.position.2 as i8) + self.direction.2) as u8)
-            .clamp(Self::PADDING, voxelbox::DEEPTH - 1 - Self::PADDING);
-        self.position = (x, y, z);
+            .clamp(Self::PADDING, self.bounds.depth - 1 - Self::PADDING);
+        (x, y, z)
+    }
+
+    pub fn apply_movement(&mut self) {
+        self.position = self.next_position();
+    }
+
+    /// A smashed ball immediately moves with its fastest speed
+    pub fn smash(&mut self) {
+        self.movement_intervall = Duration::from_millis(self.speed.fastest_ms);
     }
 
     pub fn handle_collision(&mut self) {
         self.collisions_since_speed_inc += 1;
-        if self.collisions_since_speed_inc >= COLLISIONS_UNTIL_SPEED_INC {
+        if self.collisions_since_speed_inc >= self.speed.collisions_until_speed_up.get() {
             self.collisions_since_speed_inc = 0;
             let current_ms = self.movement_intervall.as_millis() as f64;
-            let decrease = (current_ms - MIN_BALL_SLEEP_TIME) / 3.0;
+            let decrease = (current_ms - self.speed.fastest_ms as f64) / 3.0;
             let new_ms = (current_ms - decrease) as u64;
             self.movement_intervall = Duration::from_millis(new_ms);
         }
     }
 
+    /// Bounces the ball off the paddle in front of the wall at `site`
+    pub fn bounce_off(&mut self, site: &PlayerSite) {
+        match site {
+            PlayerSite::Left | PlayerSite::Right => self.change_direction((true, false, false)),
+            PlayerSite::Top | PlayerSite::Bottom => {
+                if self.direction.1 == 0 {
+                    self.direction.1 = site.wall_direction();
+                }
+                self.change_direction((false, true, false));
+            }
+        }
+    }
+
     pub fn change_direction(&mut self, (x_collides, y_collides, z_collides): (bool, bool, bool)) {
         let mut direction = self.direction;
-        let mut rng = rand::rng();
 
         if x_collides {
             direction.0 = -(direction.0);
@@ -70,12 +206,14 @@ impl Ball {
         if x_collides || z_collides || y_collides {
             let both_are_straight = direction.1 == 0 && direction.2 == 0;
             let probability = if both_are_straight { 0.7 } else { 0.4 };
-            if rng.random_bool(probability) && direction.1 == 0 {
-                direction.1 += if rng.random_bool(0.5) { 1 } else { -1 };
-            }
-            if rng.random_bool(probability) && direction.2 == 0 {
-                direction.2 += if rng.random_bool(0.5) { 1 } else { -1 };
-            }
+            random::with_rng(|rng| {
+                if rng.random_bool(probability) && direction.1 == 0 {
+                    direction.1 += if rng.random_bool(0.5) { 1 } else { -1 };
+                }
+                if rng.random_bool(probability) && direction.2 == 0 {
+                    direction.2 += if rng.random_bool(0.5) { 1 } else { -1 };
+                }
+            });
         }
 
         self.direction = direction;
@@ -88,6 +226,10 @@ impl Draw for Ball {
     }
 
     fn draw(&self) -> Vec<(usize, usize, usize)> {
+        if self.effects.contains(&BallEffect::Invisible) {
+            return Vec::new();
+        }
+
         let (x, y, z) = self.position;
 
         let mut result = Vec::with_capacity(Self::PADDING.pow(3).into());
@@ -110,16 +252,12 @@ impl Draw for Ball {
 
 impl Default for Ball {
     fn default() -> Self {
-        Self {
-            position: (
-                voxelbox::WIDTH / 2,
-                voxelbox::HEIGHT / 2,
-                voxelbox::DEEPTH / 2,
-            ),
-            color: Rgb::pink(),
-            direction: (NonZero::new(1).unwrap(), 0, 0),
-            collisions_since_speed_inc: 0,
-            movement_intervall: Duration::from_millis(MAX_BALL_SLEEP_TIME.round() as u64),
-        }
+        let bounds = Bounds::default();
+        Self::new(
+            bounds.center(),
+            Rgb::pink(),
+            bounds,
+            SpeedProfile::default(),
+        )
     }
 }
@@ -2,71 +2,176 @@ use crate::log;
 
 use super::{
     ball::Ball,
-    collision::{Bounds, Collision, CollisionSide},
-    player::Player,
+    collision::{Collision, CollisionSide},
+    level::Level,
+    obstacle::Obstacle,
+    player::{Player, PlayerSite},
     state,
 };
-use std::{num::NonZero, time::Instant};
+use std::time::Instant;
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum BallEvent {
+    PaddleHit,
+    /// The ball reached the wall behind the paddle of a player
+    Conceded(state::Player),
+}
+
+/// A ball reached the wall of `conceded`, `hit_by` is the player who hit it the last time
+#[derive(Debug, PartialEq, Eq)]
+pub struct Goal {
+    pub conceded: state::Player,
+    pub hit_by: Option<state::Player>,
+}
+
+/// Moves every ball whose movement intervall elapsed, returns the first goal
+/// and the number of paddle hits
 pub fn handle_ball_movement_and_score(
-    ball: &mut Ball,
-    player_1: &Player,
-    player_2: &Player,
-    last_move: &mut Instant,
-) -> Option<state::Player> {
-    let now = Instant::now();
+    balls: &mut [(Ball, Instant)],
+    players: &[Player],
+    obstacles: &[Obstacle],
+    now: Instant,
+) -> (Option<Goal>, u8) {
+    let mut goal = None;
+    let mut paddle_hits = 0;
 
-    if now.duration_since(*last_move) >= ball.movement_intervall {
+    for i in 0..balls.len() {
+        let (before, rest) = balls.split_at_mut(i);
+        let ((ball, last_move), after) = rest.split_first_mut().unwrap();
+        if now.duration_since(*last_move) < ball.intervall() {
+            continue;
+        }
         *last_move = now;
 
-        let p1_collision = ball.collides(player_1);
-        let p2_collision = ball.collides(player_2);
-        let colliding_sides = ball.collides(&Bounds);
+        let others = before.iter().chain(after.iter()).map(|(ball, _)| ball);
+        match move_ball_and_score(ball, players, others, obstacles) {
+            Some(BallEvent::PaddleHit) => paddle_hits += 1,
+            Some(BallEvent::Conceded(conceded)) => {
+                goal.get_or_insert(Goal {
+                    conceded,
+                    hit_by: ball.last_hit(),
+                });
+            }
+            None => {}
+        }
+    }
 
-        ball.change_direction((
-            p1_collision || p2_collision,
-            colliding_sides.contains(&CollisionSide::Top)
-                || colliding_sides.contains(&CollisionSide::Bottom),
-            colliding_sides.contains(&CollisionSide::Front)
-                || colliding_sides.contains(&CollisionSide::Back),
-        ));
-        if (!colliding_sides.is_empty()) || p1_collision || p2_collision {
-            ball.handle_collision();
+    (goal, paddle_hits)
+}
+
+/// Moves the ball a single step, the walls, obstacles and other balls are resolved before the paddles
+/// so that the paddles are checked against the direction the ball will actually take.
+/// The ball bounces off every wall without a player behind it
+pub fn move_ball_and_score<'a>(
+    ball: &mut Ball,
+    players: &[Player],
+    other_balls: impl Iterator<Item = &'a Ball>,
+    obstacles: &[Obstacle],
+) -> Option<BallEvent> {
+    let colliding_sides = ball.collides(ball.bounds());
+    let guarded_by = |side: &CollisionSide| {
+        PlayerSite::from_side(side)
+            .and_then(|site| players.iter().find(|player| *player.site() == site))
+    };
+    let bounces = |sides: [CollisionSide; 2]| {
+        sides
+            .iter()
+            .any(|side| colliding_sides.contains(side) && guarded_by(side).is_none())
+    };
+    ball.change_direction((
+        bounces([CollisionSide::Left, CollisionSide::Right]),
+        bounces([CollisionSide::Top, CollisionSide::Bottom]),
+        bounces([CollisionSide::Front, CollisionSide::Back]),
+    ));
+
+    let mut obstacle_collision = false;
+    for obstacle in obstacles {
+        let axes = ball.collides(obstacle);
+        if axes.0 || axes.1 || axes.2 {
+            obstacle_collision = true;
+            ball.change_direction(axes);
         }
-        ball.apply_movement();
+    }
 
-        if colliding_sides.contains(&CollisionSide::Right) {
-            Some(state::Player::Player1)
-        } else if colliding_sides.contains(&CollisionSide::Left) {
-            Some(state::Player::Player2)
-        } else {
-            None
+    let mut ball_collision = false;
+    for other in other_balls {
+        let axes = ball.collides(other);
+        if axes.0 || axes.1 || axes.2 {
+            ball_collision = true;
+            ball.change_direction(axes);
         }
+    }
+
+    // In a corner the bounce off one paddle can send the ball into the paddle on the other wall,
+    // a bounced ball moves away from the wall so every wall is resolved at most once
+    let mut paddle_collision = false;
+    while let Some(paddle) = players.iter().find(|player| ball.collides(*player)) {
+        paddle_collision = true;
+        ball.hit_by(paddle.id());
+        if paddle.is_smashing() {
+            ball.smash();
+        }
+        ball.bounce_off(paddle.site());
+    }
+
+    if (!colliding_sides.is_empty()) || obstacle_collision || ball_collision || paddle_collision {
+        ball.handle_collision();
+    }
+    ball.apply_movement();
+
+    if let Some(player) = colliding_sides.iter().find_map(guarded_by) {
+        Some(BallEvent::Conceded(player.id()))
+    } else if paddle_collision {
+        Some(BallEvent::PaddleHit)
     } else {
         None
     }
 }
 
 pub fn update_game_state_and_reset(
-    player: &state::Player,
+    goal: &Goal,
     state: &mut state::GameState,
-    winning_points: NonZero<u8>,
-) -> (Player, Player, Ball) {
-    state.score(player);
-    log!(Log, "{} Scored ({})", player, state.fmt_score());
-
-    if let Some(winner) = state.winner(winning_points) {
-        log!(Success, "{} won ({})", winner, state.fmt_score());
-        std::process::exit(0);
+    level: &Level,
+) -> (Vec<Player>, Ball) {
+    match state.score_goal(&goal.conceded, goal.hit_by) {
+        state::GoalResult::Scored(scorers) => {
+            for scorer in scorers {
+                log!(Log, "{} Scored ({})", scorer, state.fmt_score());
+            }
+        }
+        state::GoalResult::WonSet(scorer) => {
+            log!(Success, "{} won the set ({})", scorer, state.fmt_score());
+        }
+        state::GoalResult::WonMatch(scorer) => {
+            log!(
+                Log,
+                "{} scored the winning point ({})",
+                scorer,
+                state.fmt_score()
+            );
+        }
+        state::GoalResult::LostLife(scorer) => {
+            log!(Log, "{} lost a life ({})", scorer, state.fmt_score());
+        }
+        state::GoalResult::Eliminated(scorer) => {
+            log!(Log, "{} is eliminated ({})", scorer, state.fmt_score());
+        }
     }
+    reset_after_goal(goal, state, level)
+}
 
-    let new_x = match player {
-        state::Player::Player1 => NonZero::new(1).unwrap(),
-        state::Player::Player2 => NonZero::new(-1).unwrap(),
+/// The players still in the game and a ball served to the player who conceded the goal
+pub fn reset_after_goal(
+    goal: &Goal,
+    state: &state::GameState,
+    level: &Level,
+) -> (Vec<Player>, Ball) {
+    // The walls of eliminated players turn solid
+    let mut players = level.players();
+    players.retain(|player| !state.is_eliminated(&player.id()));
+    let ball = match players.iter().find(|player| player.id() == goal.conceded) {
+        Some(player) => level.ball().served_to(player.site()),
+        None => level.ball(),
     };
-    (
-        Player::player_1(),
-        Player::player_2(),
-        Ball::new_with_x(new_x),
-    )
+    (players, ball)
 }
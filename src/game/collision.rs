@@ -1,8 +1,7 @@
-use super::{
-    ball::Ball,
-    player::{Player, DRAWING_DELTAS},
-};
-use crate::{dynamic_vec, plus_minus, voxelbox};
+use serde::Deserialize;
+
+use super::{ball::Ball, obstacle::Obstacle, player::Player};
+use crate::{dynamic_vec, voxelbox};
 
 pub trait Collision<T> {
     type Output;
@@ -19,45 +18,465 @@ pub enum CollisionSide {
     Back,
 }
 
-pub struct Bounds;
+/// The size of the arena, it starts at the origin of the voxelbox
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bounds {
+    pub width: u8,
+    pub height: u8,
+    pub depth: u8,
+}
+
+impl Bounds {
+    pub const fn center(&self) -> (u8, u8, u8) {
+        (self.width / 2, self.height / 2, self.depth / 2)
+    }
+
+    pub fn contains(&self, (x, y, z): (i16, i16, i16)) -> bool {
+        (0..self.width as i16).contains(&x)
+            && (0..self.height as i16).contains(&y)
+            && (0..self.depth as i16).contains(&z)
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            width: voxelbox::WIDTH,
+            height: voxelbox::HEIGHT,
+            depth: voxelbox::DEEPTH,
+        }
+    }
+}
 
 impl Collision<Bounds> for Ball {
     type Output = Vec<CollisionSide>;
-    fn collides(&self, _: &Bounds) -> Self::Output {
+    fn collides(&self, other: &Bounds) -> Self::Output {
         let (x, y, z) = self.position;
 
         let padding = Self::PADDING;
         dynamic_vec! {
             x == padding=> CollisionSide::Left,
-            x == voxelbox::WIDTH - 1 - padding => CollisionSide::Right,
+            x == other.width - 1 - padding => CollisionSide::Right,
             y == padding => CollisionSide::Top,
-            y == voxelbox::HEIGHT - 1 - padding => CollisionSide::Bottom,
+            y == other.height - 1 - padding => CollisionSide::Bottom,
             z == padding => CollisionSide::Front,
-            z == voxelbox::DEEPTH - 1 - padding => CollisionSide::Back,
+            z == other.depth - 1 - padding => CollisionSide::Back,
+        }
+    }
+}
+
+impl Collision<Ball> for Ball {
+    type Output = (bool, bool, bool);
+    /// Returns the axes on which the ball would move into the other ball
+    fn collides(&self, other: &Ball) -> Self::Output {
+        let (x, y, z) = self.next_position();
+        let (other_x, other_y, other_z) = other.position;
+        let reach = 2 * Self::PADDING as i8;
+        let overlaps = |own: u8, other: u8| (own as i8 - other as i8).abs() <= reach;
+
+        if !(overlaps(x, other_x) && overlaps(y, other_y) && overlaps(z, other_z)) {
+            return (false, false, false);
+        }
+
+        let (own_x, own_y, own_z) = self.position;
+        let (dx, dy, dz) = self.direction();
+        let towards =
+            |own: u8, other: u8, d: i8| d != 0 && (other as i8 - own as i8).signum() == d.signum();
+        (
+            towards(own_x, other_x, dx),
+            towards(own_y, other_y, dy),
+            towards(own_z, other_z, dz),
+        )
+    }
+}
+
+impl Collision<Obstacle> for Ball {
+    type Output = (bool, bool, bool);
+    /// Returns the axes on which the ball would move into the obstacle
+    fn collides(&self, other: &Obstacle) -> Self::Output {
+        let voxels = other.voxels();
+        let padding = Self::PADDING as i8;
+        let hits = |(x, y, z): (u8, u8, u8)| {
+            let overlaps = |ball: u8, voxel: u8| (ball as i8 - voxel as i8).abs() <= padding;
+            voxels
+                .iter()
+                .any(|&(vx, vy, vz)| overlaps(x, vx) && overlaps(y, vy) && overlaps(z, vz))
+        };
+
+        let (x, y, z) = self.position;
+        let (next_x, next_y, next_z) = self.next_position();
+        if !hits((next_x, next_y, next_z)) {
+            return (false, false, false);
+        }
+
+        let axes = (
+            hits((next_x, y, z)),
+            hits((x, next_y, z)),
+            hits((x, y, next_z)),
+        );
+        if axes == (false, false, false) {
+            // Only the diagonal movement hits an edge of the obstacle
+            (next_x != x, next_y != y, next_z != z)
+        } else {
+            axes
         }
     }
 }
 
 impl Collision<Player> for Ball {
     type Output = bool;
+    /// Checks whether the ball would overlap the paddle after its next movement,
+    /// only balls moving towards the wall behind the paddle can collide
     fn collides(&self, other: &Player) -> Self::Output {
-        let (x, y, z) = self.position;
-        let padding = Self::PADDING;
-        let ((player_x1, player_x2), player_y, player_z) = other.full_position();
-        let x1 = x + padding;
-        let x2 = x - padding;
-
-        let values = plus_minus!(player_x1 as i8, player_x2 as i8);
-        let x_is_matching = values.contains(&(x1 as i8)) || values.contains(&(x2 as i8));
-        if !x_is_matching {
+        if !other.site().is_approached(self.direction()) {
             return false;
         }
 
-        (*DRAWING_DELTAS).iter().any(|(y_offset, z_offset)| {
-            let pad_y = player_y as i8 + y_offset;
-            let pad_z = player_z as i8 + z_offset;
-
-            plus_minus!(pad_y).contains(&(y as i8)) && plus_minus!(pad_z).contains(&(z as i8))
+        let next = self.next_position();
+        other.voxels().into_iter().any(|voxel| {
+            [(next.0, voxel.0), (next.1, voxel.1), (next.2, voxel.2)]
+                .iter()
+                .all(|&(ball, voxel)| ball.abs_diff(voxel) <= Self::PADDING)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{
+            ball_movement::BallEvent,
+            level::TeamLayout,
+            obstacle::{ObstacleDefinition, Shape},
+            scenario::Scenario,
+            state,
+        },
+        voxelbox::Draw,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::{collections::HashSet, num::NonZero};
+
+    #[test]
+    fn test_bounds() {
+        let center = Scenario::new().ball((10, 10, 6), (1, 0, 0));
+        assert_eq!(center.bounds(), vec![]);
+
+        let left = Scenario::new().ball((1, 10, 6), (-1, 0, 0));
+        assert_eq!(left.bounds(), vec![CollisionSide::Left]);
+
+        let right = Scenario::new().ball((18, 10, 6), (1, 0, 0));
+        assert_eq!(right.bounds(), vec![CollisionSide::Right]);
+
+        let near_corner = Scenario::new().ball((1, 1, 1), (-1, -1, -1));
+        assert_eq!(
+            near_corner.bounds(),
+            vec![
+                CollisionSide::Left,
+                CollisionSide::Top,
+                CollisionSide::Front
+            ]
+        );
+
+        let far_corner = Scenario::new().ball((18, 18, 10), (1, 1, 1));
+        assert_eq!(
+            far_corner.bounds(),
+            vec![
+                CollisionSide::Right,
+                CollisionSide::Bottom,
+                CollisionSide::Back
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paddles() {
+        let scenario = Scenario::new().player_1(10, 6).player_2(10, 6);
+
+        let head_on = scenario.ball((3, 10, 6), (-1, 0, 0));
+        assert_eq!(head_on.paddles(), (true, false));
+
+        let moving_away = head_on.ball((3, 10, 6), (1, 0, 0));
+        assert_eq!(moving_away.paddles(), (false, false));
+
+        let too_far = moving_away.ball((4, 10, 6), (-1, 0, 0));
+        assert_eq!(too_far.paddles(), (false, false));
+
+        let right = too_far.ball((16, 10, 6), (1, 0, 0));
+        assert_eq!(right.paddles(), (false, true));
+    }
+
+    #[test]
+    fn test_paddle_edges() {
+        let scenario = Scenario::new().player_1(10, 6);
+
+        // The tip of the diamond is at (12, 6)
+        let tip = scenario.ball((3, 13, 6), (-1, 0, 0));
+        assert_eq!(tip.paddles(), (true, false));
+
+        let beside_tip = tip.ball((3, 14, 6), (-1, 0, 0));
+        assert_eq!(beside_tip.paddles(), (false, false));
+
+        // The corners of the bounding square aren't part of the diamond
+        let corner = beside_tip.ball((3, 13, 9), (-1, 0, 0));
+        assert_eq!(corner.paddles(), (false, false));
+
+        let moving_into = corner.ball((3, 6, 6), (-1, 1, 0));
+        assert_eq!(moving_into.paddles(), (true, false));
+
+        let moving_past = moving_into.ball((3, 7, 6), (-1, -1, 0));
+        assert_eq!(moving_past.paddles(), (false, false));
+    }
+
+    #[test]
+    fn test_balls() {
+        let ball = |position, (x, y, z)| {
+            let mut ball = Ball::default().with_direction((NonZero::new(x).unwrap(), y, z));
+            ball.position = position;
+            ball
+        };
+        let other = ball((12, 10, 6), (-1, 0, 0));
+
+        let head_on = ball((10, 10, 6), (1, 0, 0));
+        assert_eq!(head_on.collides(&other), (true, false, false));
+
+        let moving_away = ball((10, 10, 6), (-1, 0, 0));
+        assert_eq!(moving_away.collides(&other), (false, false, false));
+
+        let too_far = ball((8, 10, 6), (1, 0, 0));
+        assert_eq!(too_far.collides(&other), (false, false, false));
+
+        let diagonal = ball((10, 8, 6), (1, 1, 0));
+        assert_eq!(diagonal.collides(&other), (true, true, false));
+    }
+
+    #[test]
+    fn test_obstacles() {
+        let pillar = || {
+            Scenario::new().obstacle(ObstacleDefinition {
+                shape: Shape::Pillar { x: 10, z: 6 },
+                motion: None,
+                color: None,
+            })
+        };
+        let head_on = pillar().ball((8, 10, 6), (1, 0, 0));
+        assert_eq!(
+            head_on.ball.collides(&head_on.obstacles[0]),
+            (true, false, false)
+        );
+
+        let wall = Scenario::new().obstacle(ObstacleDefinition {
+            shape: Shape::Wall {
+                x: 10,
+                hole: (10, 6),
+                hole_size: 5,
+            },
+            motion: None,
+            color: None,
+        });
+        let through_hole = wall.ball((8, 10, 6), (1, 0, 0));
+        let obstacle = &through_hole.obstacles[0];
+        assert_eq!(through_hole.ball.collides(obstacle), (false, false, false));
+        let next_to_hole = through_hole.ball((8, 4, 6), (1, 0, 0));
+        let obstacle = &next_to_hole.obstacles[0];
+        assert_eq!(next_to_hole.ball.collides(obstacle), (true, false, false));
+
+        let edge = Scenario::new().obstacle(ObstacleDefinition {
+            shape: Shape::Block {
+                from: (10, 12, 6),
+                to: (10, 12, 6),
+            },
+            motion: None,
+            color: None,
+        });
+        let diagonal = edge.ball((8, 10, 6), (1, 1, 0));
+        assert_eq!(
+            diagonal.ball.collides(&diagonal.obstacles[0]),
+            (true, true, false)
+        );
+
+        let mut bounce = pillar().ball((8, 10, 6), (1, 0, 0));
+        assert_eq!(bounce.step(), None);
+        assert_eq!(bounce.ball.position.0, 7);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut bounce = Scenario::new().player_1(10, 6).ball((3, 10, 6), (-1, 0, 0));
+        assert_eq!(bounce.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(bounce.ball.position.0, 4);
+
+        let mut miss = Scenario::new().player_1(3, 3).ball((2, 15, 9), (-1, 0, 0));
+        assert_eq!(miss.step(), None);
+        assert_eq!(miss.ball.position.0, 1);
+        assert_eq!(
+            miss.step(),
+            Some(BallEvent::Conceded(state::Player::Player1))
+        );
+
+        let mut score = Scenario::new().player_2(3, 3).ball((18, 15, 9), (1, 0, 0));
+        assert_eq!(
+            score.step(),
+            Some(BallEvent::Conceded(state::Player::Player2))
+        );
+    }
+
+    #[test]
+    fn test_pushed_paddle() {
+        let mut front = Scenario::new()
+            .push_player_1(4)
+            .player_1(10, 6)
+            .ball((7, 10, 6), (-1, 0, 0));
+        assert_eq!(front.players[0].full_position().0, (4, 5));
+        assert_eq!(front.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(front.ball.position.0, 8);
+
+        // The ball passes through the back of the paddle
+        let mut behind = Scenario::new()
+            .push_player_1(4)
+            .player_1(10, 6)
+            .ball((2, 10, 6), (1, 0, 0));
+        assert_eq!(behind.step(), None);
+        assert_eq!(behind.ball.position.0, 3);
+
+        let mut smash = Scenario::new()
+            .push_player_1(4)
+            .player_1(10, 6)
+            .ball((7, 10, 6), (-1, 0, 0));
+        let intervall = smash.ball.intervall();
+        smash.players[0].set_smashing(true);
+        assert_eq!(smash.step(), Some(BallEvent::PaddleHit));
+        assert!(smash.ball.intervall() < intervall);
+    }
+
+    #[test]
+    fn test_four_players() {
+        // Without players on the top wall the ball bounces off it
+        let mut two = Scenario::new().ball((10, 1, 6), (1, -1, 0));
+        assert_eq!(two.step(), None);
+        assert_eq!(two.ball.direction().1, 1);
+
+        let mut miss = Scenario::four_players()
+            .player(state::Player::Player3, 3, 3)
+            .ball((10, 1, 6), (1, -1, 0));
+        assert_eq!(
+            miss.step(),
+            Some(BallEvent::Conceded(state::Player::Player3))
+        );
+
+        let mut bounce = Scenario::four_players()
+            .player(state::Player::Player4, 10, 6)
+            .ball((10, 16, 6), (1, 1, 0));
+        assert_eq!(bounce.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(bounce.ball.direction().1, -1);
+        assert_eq!(bounce.ball.last_hit(), Some(state::Player::Player4));
+    }
+
+    #[test]
+    fn test_teams() {
+        let mut forward = Scenario::teams(TeamLayout::Goalie)
+            .player_1(10, 6)
+            .ball((5, 10, 6), (-1, 0, 0));
+        assert_eq!(forward.players[0].full_position().0, (2, 3));
+        assert_eq!(forward.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(forward.ball.last_hit(), Some(state::Player::Player1));
+
+        let mut goalie = Scenario::teams(TeamLayout::Goalie)
+            .player_1(3, 3)
+            .player(state::Player::Player3, 10, 6)
+            .ball((3, 10, 6), (-1, 0, 0));
+        assert_eq!(goalie.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(goalie.ball.last_hit(), Some(state::Player::Player3));
+
+        let side_by_side = Scenario::teams(TeamLayout::SideBySide);
+        assert_eq!(side_by_side.players[0].full_position(), ((0, 1), 5, 6));
+        assert_eq!(side_by_side.players[2].full_position(), ((0, 1), 14, 6));
+        let mut lower = side_by_side.ball((3, 14, 6), (-1, 0, 0));
+        assert_eq!(lower.step(), Some(BallEvent::PaddleHit));
+        assert_eq!(lower.ball.last_hit(), Some(state::Player::Player3));
+    }
+
+    /// Moves every paddle and the ball to random positions
+    fn fuzzed(rng: &mut StdRng, mut scenario: Scenario) -> Scenario {
+        let sign = |rng: &mut StdRng| if rng.random_bool(0.5) { 1 } else { -1 };
+
+        for player in state::Player::ALL.into_iter().take(scenario.players.len()) {
+            scenario = scenario.player(player, rng.random_range(2..=17), rng.random_range(2..=9));
+        }
+        scenario.ball(
+            (
+                rng.random_range(5..=14),
+                rng.random_range(3..=16),
+                rng.random_range(1..=10),
+            ),
+            (
+                sign(rng),
+                rng.random_range(-1..=1),
+                rng.random_range(-1..=1),
+            ),
+        )
+    }
+
+    /// Plays the scenario until a goal and checks that the ball stays in the box.
+    /// `solid` lists the voxels the ball must not enter after every step
+    fn rally(mut scenario: Scenario, solid: impl Fn(&Scenario) -> HashSet<(usize, usize, usize)>) {
+        for _ in 0..500 {
+            if let Some(BallEvent::Conceded(_)) = scenario.step() {
+                break;
+            }
+
+            let ball = scenario.ball.draw();
+            assert!(
+                ball.iter().all(|&(x, y, z)| x < voxelbox::WIDTH.into()
+                    && y < voxelbox::HEIGHT.into()
+                    && z < voxelbox::DEEPTH.into()),
+                "The ball left the box at {:?}",
+                scenario.ball.position
+            );
+            let solid = solid(&scenario);
+            assert!(
+                ball.iter().all(|voxel| !solid.contains(voxel)),
+                "The ball passed through a paddle at {:?}",
+                scenario.ball.position
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzzed_rallies() {
+        let mut rng = StdRng::seed_from_u64(0x9090);
+
+        for run in 0..2_000 {
+            let scenario = match run % 3 {
+                0 => Scenario::new(),
+                1 => Scenario::four_players(),
+                _ => Scenario::teams(TeamLayout::SideBySide),
+            };
+            rally(fuzzed(&mut rng, scenario), |scenario| {
+                scenario.players.iter().flat_map(Draw::draw).collect()
+            });
+        }
+    }
+
+    #[test]
+    fn test_fuzzed_goalie_rallies() {
+        let mut rng = StdRng::seed_from_u64(0x6041);
+
+        for _ in 0..1_000 {
+            let scenario = fuzzed(&mut rng, Scenario::teams(TeamLayout::Goalie));
+            // The goalies are never passed, a ball cleared by a goalie may pass through the
+            // back of the forward in front of it but never enters a forward from the front
+            rally(scenario, |scenario| {
+                let direction = scenario.ball.direction();
+                let (forwards, goalies) = scenario.players.split_at(2);
+                forwards
+                    .iter()
+                    .filter(|forward| forward.site().is_approached(direction))
+                    .chain(goalies)
+                    .flat_map(Draw::draw)
+                    .collect()
+            });
+        }
+    }
+}
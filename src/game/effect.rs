@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+/// Effects which wear off after a while, the same effect can be active multiple times
+#[derive(Clone, Debug)]
+pub struct TimedEffects<T> {
+    effects: Vec<(T, Instant)>,
+}
+
+impl<T> Default for TimedEffects<T> {
+    fn default() -> Self {
+        Self {
+            effects: Vec::new(),
+        }
+    }
+}
+
+impl<T: PartialEq> TimedEffects<T> {
+    pub fn add(&mut self, effect: T, until: Instant) {
+        self.effects.push((effect, until));
+    }
+
+    /// Removes every expired effect, returns whether any effect expired
+    pub fn update(&mut self, now: Instant) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|(_, until)| *until > now);
+        before != self.effects.len()
+    }
+
+    pub fn count(&self, effect: &T) -> usize {
+        self.effects.iter().filter(|(e, _)| e == effect).count()
+    }
+
+    pub fn contains(&self, effect: &T) -> bool {
+        self.count(effect) > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timed_effects() {
+        let now = Instant::now();
+        let mut effects = TimedEffects::default();
+        effects.add('a', now + Duration::from_secs(1));
+        effects.add('a', now + Duration::from_secs(3));
+        effects.add('b', now + Duration::from_secs(2));
+
+        assert_eq!(effects.count(&'a'), 2);
+        assert!(!effects.update(now));
+
+        assert!(effects.update(now + Duration::from_secs(2)));
+        assert_eq!(effects.count(&'a'), 1);
+        assert!(!effects.contains(&'b'));
+
+        assert!(effects.update(now + Duration::from_secs(3)));
+        assert!(!effects.contains(&'a'));
+    }
+}
@@ -1,42 +1,285 @@
-use crate::input::{GameInput, Movement};
+use crate::input::{Deflection, GameInput, Velocity};
 
 use super::player::Player;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-pub struct PlayerMovementTimestamps {
-    x: Instant,
-    y: Instant,
+/// The maximum speed of a paddle in voxels per second
+const MAX_SPEED: f32 = 12.0;
+/// How fast a paddle speeds up and slows down in voxels per second squared
+const ACCELERATION: f32 = 80.0;
+/// How fast a paddle in the absolute mode closes the gap to its target, per second
+const APPROACH_RATE: f32 = 10.0;
+const DEPTH_STEP_TIME: Duration = Duration::from_millis(60);
+/// A paddle hitting the ball within this time after being pushed forward smashes it
+const SMASH_TIME: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlMode {
+    /// The stick controls the velocity of the paddle
+    #[default]
+    Velocity,
+    /// The stick position maps to a position of the paddle, a centered stick centers the paddle
+    Absolute,
+}
+
+impl std::str::FromStr for ControlMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "velocity" => Ok(Self::Velocity),
+            "absolute" => Ok(Self::Absolute),
+            _ => Err(format!(
+                "{s} isn't a control mode, use velocity or absolute"
+            )),
+        }
+    }
 }
 
-impl Default for PlayerMovementTimestamps {
-    fn default() -> Self {
+/// The continuous position and velocity of a paddle, the paddle is drawn at the nearest voxel.
+/// The `x` axis of the input moves the paddle along the z axis of the arena
+pub struct PlayerMotion {
+    mode: ControlMode,
+    /// `None` until the position was taken from the paddle
+    position: Option<(f32, f32)>,
+    velocity: (f32, f32),
+    last_update: Instant,
+    depth: Instant,
+    push: Instant,
+}
+
+impl PlayerMotion {
+    pub fn new(mode: ControlMode) -> Self {
         let now = Instant::now();
-        Self { x: now, y: now }
+        Self {
+            mode,
+            position: None,
+            velocity: (0.0, 0.0),
+            last_update: now,
+            depth: now,
+            push: now,
+        }
+    }
+
+    /// Moves `current` towards `target` by at most `max_change`
+    fn approach(current: f32, target: f32, max_change: f32) -> f32 {
+        current + (target - current).clamp(-max_change, max_change)
+    }
+
+    /// The velocity moving the paddle to the position the stick points at
+    fn towards(deflection: Deflection, player: &Player, (x, y): (f32, f32)) -> Velocity {
+        let ((lower_y, upper_y), (lower_z, upper_z)) = player.limits();
+        // A centered stick moves the paddle to its spawn in the center of the wall
+        let target = |deflection: f32, lower: u8, upper: u8| {
+            let center = (lower + upper).div_ceil(2);
+            let reach = if deflection > 0.0 {
+                upper - center
+            } else {
+                center - lower
+            };
+            center as f32 + deflection * reach as f32
+        };
+        let velocity = |target: f32, position: f32| {
+            ((target - position) * APPROACH_RATE / MAX_SPEED).clamp(-1.0, 1.0)
+        };
+
+        Velocity {
+            x: velocity(target(deflection.x, lower_z, upper_z), x),
+            y: velocity(target(deflection.y, lower_y, upper_y), y),
+        }
+    }
+
+    fn update(
+        &mut self,
+        (velocity, deflection): (Velocity, Deflection),
+        player: &mut Player,
+        now: Instant,
+    ) {
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        // The paddle was moved by something else, e.g. a reset after a point or a resize
+        let (_, y, z) = player.full_position();
+        let voxel = (z as f32, y as f32);
+        let (x_position, y_position) = match self.position {
+            Some((x, y)) if (x.round(), y.round()) == voxel => (x, y),
+            _ => {
+                self.velocity = (0.0, 0.0);
+                voxel
+            }
+        };
+
+        let velocity = match self.mode {
+            ControlMode::Velocity => velocity,
+            ControlMode::Absolute => Self::towards(deflection, player, (x_position, y_position)),
+        };
+
+        let max_change = ACCELERATION * dt;
+        self.velocity = (
+            Self::approach(self.velocity.0, velocity.x * MAX_SPEED, max_change),
+            Self::approach(self.velocity.1, velocity.y * MAX_SPEED, max_change),
+        );
+        let target = (
+            x_position + self.velocity.0 * dt,
+            y_position + self.velocity.1 * dt,
+        );
+
+        player.inc_x(target.0.round() as i16 - z as i16);
+        player.inc_y(target.1.round() as i16 - y as i16);
+
+        // Stop at the walls instead of pushing against them
+        let (_, y, z) = player.full_position();
+        let x_position = if target.0.round() == z as f32 {
+            target.0
+        } else {
+            self.velocity.0 = 0.0;
+            z as f32
+        };
+        let y_position = if target.1.round() == y as f32 {
+            target.1
+        } else {
+            self.velocity.1 = 0.0;
+            y as f32
+        };
+        self.position = Some((x_position, y_position));
     }
 }
 
 pub fn handle_player_input<T: GameInput>(
     input: &T,
     player: &mut Player,
-    last_moved: &mut PlayerMovementTimestamps,
+    motion: &mut PlayerMotion,
+    now: Instant,
 ) {
-    let activation_durations = input.activation_times();
-    let Movement { x, y } = input.movement();
-    let now = Instant::now();
-
-    if activation_durations
-        .x
-        .is_some_and(|x| now.duration_since(last_moved.x) >= x)
-    {
-        last_moved.x = now;
-        player.inc_x(x);
-    }
-
-    if activation_durations
-        .y
-        .is_some_and(|y| now.duration_since(last_moved.y) >= y)
-    {
-        last_moved.y = now;
-        player.inc_y(y);
+    motion.update((input.velocity(), input.deflection()), player, now);
+
+    if player.max_depth() > 0 && now.duration_since(motion.depth) >= DEPTH_STEP_TIME {
+        let push = input.push().unwrap_or(0.0).clamp(0.0, 1.0);
+        let target = (push * player.max_depth() as f32).round() as i16;
+        let step = (target - player.depth() as i16).signum();
+        if step != 0 {
+            motion.depth = now;
+            player.inc_depth(step);
+        }
+        if step > 0 {
+            motion.push = now;
+        }
+    }
+    player.set_smashing(player.depth() > 0 && now.duration_since(motion.push) < SMASH_TIME);
+}
+
+/// Moves every remaining player with its own input. Eliminated players are removed from
+/// `players`, so the inputs and motions are indexed by the id of the player
+pub fn handle_players_input<T: GameInput>(
+    inputs: &[T],
+    players: &mut [Player],
+    motions: &mut [PlayerMotion],
+    now: Instant,
+) {
+    for player in players {
+        let index = player.id().index();
+        handle_player_input(&inputs[index], player, &mut motions[index], now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{level::Level, state},
+        input::Normalized,
+    };
+
+    struct Stick(f32, f32);
+
+    impl GameInput for Stick {
+        fn normalized(&self) -> Normalized {
+            Normalized {
+                x: Some(self.0),
+                y: Some(self.1),
+            }
+        }
+    }
+
+    #[test]
+    fn test_smooth_movement() {
+        let mut player = Level::default().player(state::Player::Player1);
+        let mut motion = PlayerMotion::new(ControlMode::Velocity);
+        let start = motion.last_update;
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let position = |player: &Player| {
+            let (_, y, z) = player.full_position();
+            (y, z)
+        };
+        assert_eq!(position(&player), (10, 6));
+
+        // Accelerating slowly, a short nudge doesn't move the paddle yet
+        handle_player_input(&Stick(0.0, -1.0), &mut player, &mut motion, at(0));
+        handle_player_input(&Stick(0.0, -1.0), &mut player, &mut motion, at(20));
+        assert_eq!(position(&player), (10, 6));
+
+        // Half a second at full deflection, the paddle reaches its maximum speed
+        for ms in (40..=520).step_by(20) {
+            handle_player_input(&Stick(0.0, -1.0), &mut player, &mut motion, at(ms));
+        }
+        assert_eq!(motion.velocity, (0.0, MAX_SPEED));
+        assert_eq!(position(&player).0, 15);
+
+        // The paddle stops at the wall and turns around quickly
+        for ms in (540..=1000).step_by(20) {
+            handle_player_input(&Stick(0.0, -1.0), &mut player, &mut motion, at(ms));
+        }
+        assert_eq!(position(&player).0, 17);
+        for ms in (1020..=1200).step_by(20) {
+            handle_player_input(&Stick(0.0, 1.0), &mut player, &mut motion, at(ms));
+        }
+        assert_eq!(position(&player).0, 16);
+
+        // A small deflection moves the paddle slowly
+        let mut slow = Level::default().player(state::Player::Player1);
+        let mut motion = PlayerMotion::new(ControlMode::Velocity);
+        for ms in (0..=1000).step_by(20) {
+            handle_player_input(&Stick(-0.25, 0.0), &mut slow, &mut motion, at(ms));
+        }
+        assert_eq!(position(&slow), (10, 9));
+    }
+
+    #[test]
+    fn test_absolute_movement() {
+        let mut player = Level::default().player(state::Player::Player1);
+        let mut motion = PlayerMotion::new(ControlMode::Absolute);
+        let start = motion.last_update;
+        let mut time = 0;
+        let mut hold = |stick: Stick, player: &mut Player| {
+            for _ in 0..100 {
+                time += 20;
+                let now = start + Duration::from_millis(time);
+                handle_player_input(&stick, player, &mut motion, now);
+            }
+            let (_, y, z) = player.full_position();
+            (y, z)
+        };
+
+        // The paddle moves to the stick position and stays there
+        assert_eq!(hold(Stick(0.0, -1.0), &mut player), (17, 6));
+        assert_eq!(hold(Stick(-1.0, 1.0), &mut player), (2, 9));
+        assert_eq!(hold(Stick(0.5, 0.5), &mut player), (6, 4));
+        assert_eq!(hold(Stick(0.0, 0.0), &mut player), (10, 6));
+    }
+
+    #[test]
+    fn test_eliminated_player() {
+        // Player 1 was eliminated, player 2 still reads the second input
+        let mut players = vec![Level::default().player(state::Player::Player2)];
+        let mut motions = [ControlMode::Velocity; 2].map(PlayerMotion::new);
+        let inputs = [Stick(0.0, 0.0), Stick(0.0, -1.0)];
+        let start = motions[1].last_update;
+        for ms in (0..=500).step_by(20) {
+            let now = start + Duration::from_millis(ms);
+            handle_players_input(&inputs, &mut players, &mut motions, now);
+        }
+
+        let (_, y, _) = players[0].full_position();
+        assert!(y > 10, "Player 2 moved with the second input");
     }
 }
@@ -0,0 +1,837 @@
+use serde::Deserialize;
+use std::{fmt, fs, path::Path};
+
+use super::{
+    ball::{Ball, SpeedProfile},
+    collision::Bounds,
+    obstacle::{Obstacle, ObstacleDefinition, Shape},
+    paddle::{self, MaskError, PaddleShape, PAD_SIZE},
+    player::{Player, PlayerSite},
+    state,
+};
+use crate::{color::Rgb, odd::Odd};
+
+const MIN_ARENA_WIDTH: u8 = 8;
+const MIN_ARENA_SIZE: u8 = 3;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BallDefinition {
+    /// Defaults to the center of the arena
+    spawn: Option<(u8, u8, u8)>,
+    color: Option<Rgb>,
+    speed: SpeedProfile,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerDefinition {
+    color: Option<Rgb>,
+    paddle_shape: Option<PaddleShape>,
+    paddle_size: Option<u8>,
+    /// The center `(y, z)` of the paddle, defaults to the center of the wall
+    spawn: Option<(u8, u8)>,
+}
+
+/// How the two players of a team share their wall, levels and the CLI accept
+/// `side-by-side` as well as `side_by_side`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamLayout {
+    /// Both paddles are smaller and start in the upper and lower half of the wall
+    #[serde(alias = "side-by-side")]
+    SideBySide,
+    /// Players 1 and 2 play forward two voxels in front of the goalies, players 3 and 4
+    Goalie,
+}
+
+impl std::str::FromStr for TeamLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "side-by-side" | "side_by_side" => Ok(Self::SideBySide),
+            "goalie" => Ok(Self::Goalie),
+            _ => Err(format!(
+                "{s} isn't a team layout, use side-by-side or goalie"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Level {
+    arena: Bounds,
+    ball: BallDefinition,
+    player_1: PlayerDefinition,
+    player_2: PlayerDefinition,
+    player_3: PlayerDefinition,
+    player_4: PlayerDefinition,
+    /// The number of players, 2 or 4 with players 3 and 4 on the top and bottom wall
+    players: Option<u8>,
+    /// Two teams of two players, players 1 and 3 on the left and players 2 and 4 on the right wall
+    teams: Option<TeamLayout>,
+    /// How many voxels the paddles can be pushed into the arena
+    paddle_depth: u8,
+    #[serde(rename = "obstacle")]
+    obstacles: Vec<ObstacleDefinition>,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    ReadError(String),
+    ParseError(String),
+    ArenaTooLarge(Bounds),
+    ArenaTooSmall(Bounds),
+    InvalidSpeed {
+        start_ms: u64,
+        fastest_ms: u64,
+    },
+    BallSpawnOutside((u8, u8, u8)),
+    InvalidPlayerCount(u8),
+    TeamsNeedFourPlayers(u8),
+    InvalidPaddleMask {
+        player: state::Player,
+        error: MaskError,
+    },
+    PaddleEven {
+        player: state::Player,
+        size: u8,
+    },
+    PaddleTooLarge {
+        player: state::Player,
+        size: u8,
+        dimension: &'static str,
+        length: u8,
+    },
+    PaddleSpawnOutside {
+        player: state::Player,
+        spawn: (u8, u8),
+    },
+    PaddleDepthTooLarge {
+        depth: u8,
+        max: u8,
+    },
+    ObstacleOutside {
+        index: usize,
+        voxel: (i16, i16, i16),
+    },
+    ObstacleCoversSpawn {
+        index: usize,
+    },
+    ObstacleStandsStill {
+        index: usize,
+        field: &'static str,
+    },
+    WallHoleEven {
+        index: usize,
+        size: u8,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt_bounds = |b: &Bounds| format!("{}x{}x{}", b.width, b.height, b.depth);
+        match self {
+            Self::ReadError(msg) => write!(f, "Could not read the level: {msg}"),
+            Self::ParseError(msg) => write!(f, "Could not parse the level: {msg}"),
+            Self::ArenaTooLarge(arena) => write!(
+                f,
+                "The arena ({}) doesn't fit into the voxelbox ({})",
+                fmt_bounds(arena),
+                fmt_bounds(&Bounds::default())
+            ),
+            Self::ArenaTooSmall(arena) => write!(
+                f,
+                "The arena ({}) is too small, it must be at least {MIN_ARENA_WIDTH}x{MIN_ARENA_SIZE}x{MIN_ARENA_SIZE}",
+                fmt_bounds(arena)
+            ),
+            Self::InvalidSpeed {
+                start_ms,
+                fastest_ms,
+            } => write!(
+                f,
+                "The fastest_ms ({fastest_ms}) of the ball must be between 1 and its start_ms ({start_ms})"
+            ),
+            Self::BallSpawnOutside(spawn) => write!(
+                f,
+                "The ball can't spawn at {spawn:?}, it must be at least {} voxel away from the walls",
+                Ball::PADDING
+            ),
+            Self::InvalidPlayerCount(players) => {
+                write!(f, "A game has 1, 2 or 4 players, got {players}")
+            }
+            Self::TeamsNeedFourPlayers(players) => {
+                write!(f, "Two teams need 4 players, got {players}")
+            }
+            Self::InvalidPaddleMask { player, error } => {
+                write!(f, "The paddle mask of {player} is invalid, {error}")
+            }
+            Self::PaddleEven { player, size } => {
+                write!(f, "The paddle of {player} must have an odd size, got {size}")
+            }
+            Self::PaddleTooLarge {
+                player,
+                size,
+                dimension,
+                length,
+            } => write!(
+                f,
+                "The paddle of {player} (size {size}) doesn't fit into the {dimension} of the arena ({length})"
+            ),
+            Self::PaddleSpawnOutside { player, spawn } => write!(
+                f,
+                "The paddle of {player} can't spawn at {spawn:?}, it would stick out of the arena"
+            ),
+            Self::PaddleDepthTooLarge { depth, max } => write!(
+                f,
+                "The paddles can be pushed at most {max} voxel into this arena, got {depth}"
+            ),
+            Self::ObstacleOutside { index, voxel } => write!(
+                f,
+                "Obstacle #{index} reaches outside of the arena at {voxel:?}"
+            ),
+            Self::ObstacleCoversSpawn { index } => {
+                write!(f, "Obstacle #{index} covers the spawn of the ball")
+            }
+            Self::ObstacleStandsStill { index, field } => {
+                write!(f, "The {field} of obstacle #{index} must be at least 1")
+            }
+            Self::WallHoleEven { index, size } => write!(
+                f,
+                "The hole in obstacle #{index} must have an odd size, got {size}"
+            ),
+        }
+    }
+}
+
+impl Level {
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        let content = fs::read_to_string(path).map_err(|e| LevelError::ReadError(e.to_string()))?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, LevelError> {
+        let level: Self =
+            toml::from_str(content).map_err(|e| LevelError::ParseError(e.to_string()))?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn validate(&self) -> Result<(), LevelError> {
+        let arena = self.arena;
+        let cube = Bounds::default();
+        if arena.width > cube.width || arena.height > cube.height || arena.depth > cube.depth {
+            return Err(LevelError::ArenaTooLarge(arena));
+        }
+        if arena.width < MIN_ARENA_WIDTH
+            || arena.height < MIN_ARENA_SIZE
+            || arena.depth < MIN_ARENA_SIZE
+        {
+            return Err(LevelError::ArenaTooSmall(arena));
+        }
+
+        let speed = self.ball.speed;
+        if speed.fastest_ms == 0 || speed.fastest_ms > speed.start_ms {
+            return Err(LevelError::InvalidSpeed {
+                start_ms: speed.start_ms,
+                fastest_ms: speed.fastest_ms,
+            });
+        }
+
+        let spawn = self.ball_spawn();
+        let padding = Ball::PADDING;
+        let spawn_fits = |value: u8, length: u8| (padding..length - padding).contains(&value);
+        if !(spawn_fits(spawn.0, arena.width)
+            && spawn_fits(spawn.1, arena.height)
+            && spawn_fits(spawn.2, arena.depth))
+        {
+            return Err(LevelError::BallSpawnOutside(spawn));
+        }
+
+        if !matches!(self.player_count(), 1 | 2 | 4) {
+            return Err(LevelError::InvalidPlayerCount(self.player_count()));
+        }
+        if self.teams.is_some() && self.player_count() != 4 {
+            return Err(LevelError::TeamsNeedFourPlayers(self.player_count()));
+        }
+        for &player in self.active_players() {
+            self.validate_player(player)?;
+        }
+        if self.paddle_depth > self.max_paddle_depth() {
+            return Err(LevelError::PaddleDepthTooLarge {
+                depth: self.paddle_depth,
+                max: self.max_paddle_depth(),
+            });
+        }
+
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            let index = index + 1;
+            let still = |field| Err(LevelError::ObstacleStandsStill { index, field });
+            if let Some(motion) = &obstacle.motion {
+                if motion.distance == 0 {
+                    return still("distance");
+                }
+                if motion.step_ms == 0 {
+                    return still("step_ms");
+                }
+            }
+            if let Shape::Bar { rotation_ms: 0, .. } = obstacle.shape {
+                return still("rotation_ms");
+            }
+            // The hole is centered, so only an odd size spans exactly `hole_size` voxels
+            if let Shape::Wall { hole_size, .. } = obstacle.shape {
+                if Odd::<u8>::new(hole_size).is_none() {
+                    return Err(LevelError::WallHoleEven {
+                        index,
+                        size: hole_size,
+                    });
+                }
+            }
+
+            // Every position the obstacle reaches while rotating and moving
+            let distance = obstacle.motion.as_ref().map_or(0, |motion| motion.distance);
+            for rotation in 0..obstacle.shape.rotations() {
+                for voxel in obstacle.shape.voxels(&arena, rotation) {
+                    for offset in 0..=distance {
+                        let voxel = obstacle
+                            .motion
+                            .as_ref()
+                            .map_or(voxel, |motion| motion.shift(voxel, offset));
+                        if !arena.contains(voxel) {
+                            return Err(LevelError::ObstacleOutside { index, voxel });
+                        }
+                        let (x, y, z) = voxel;
+                        let near =
+                            |voxel: i16, spawn: u8| (voxel - spawn as i16).abs() <= padding.into();
+                        if near(x, spawn.0) && near(y, spawn.1) && near(z, spawn.2) {
+                            return Err(LevelError::ObstacleCoversSpawn { index });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_player(&self, player: state::Player) -> Result<(), LevelError> {
+        let (definition, site, _) = self.defaults(player);
+        let shape = definition
+            .paddle_shape
+            .clone()
+            .unwrap_or(PaddleShape::Diamond);
+        shape
+            .validate()
+            .map_err(|error| LevelError::InvalidPaddleMask { player, error })?;
+
+        let size = definition
+            .paddle_size
+            .unwrap_or(self.default_paddle_size().value());
+        let Some(size) = Odd::<u8>::new(size) else {
+            return Err(LevelError::PaddleEven { player, size });
+        };
+
+        let (padding_y, padding_z) = paddle::extent(&shape.deltas(size));
+        let (length_y, length_z) = site.plane(&self.arena);
+        let dimension_y = match site {
+            PlayerSite::Left | PlayerSite::Right => "height",
+            PlayerSite::Top | PlayerSite::Bottom => "width",
+        };
+        for (dimension, padding, length) in [
+            (dimension_y, padding_y, length_y),
+            ("depth", padding_z, length_z),
+        ] {
+            let size = 2 * padding + 1;
+            if size > length {
+                return Err(LevelError::PaddleTooLarge {
+                    player,
+                    size,
+                    dimension,
+                    length,
+                });
+            }
+        }
+
+        let spawn = self.paddle_spawn(player);
+        let fits =
+            |value: u8, padding: u8, length: u8| (padding..length - padding).contains(&value);
+        if !(fits(spawn.0, padding_y, length_y) && fits(spawn.1, padding_z, length_z)) {
+            return Err(LevelError::PaddleSpawnOutside { player, spawn });
+        }
+
+        Ok(())
+    }
+
+    /// Overrides the paddle of a player and validates the level again
+    pub fn with_paddle(
+        mut self,
+        player: state::Player,
+        shape: Option<PaddleShape>,
+        size: Option<u8>,
+    ) -> Result<Self, LevelError> {
+        let definition = match player {
+            state::Player::Player1 => &mut self.player_1,
+            state::Player::Player2 => &mut self.player_2,
+            state::Player::Player3 => &mut self.player_3,
+            state::Player::Player4 => &mut self.player_4,
+        };
+        definition.paddle_shape = shape.or(definition.paddle_shape.take());
+        definition.paddle_size = size.or(definition.paddle_size);
+
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Overrides how far the paddles can be pushed into the arena and validates the level again
+    pub fn with_paddle_depth(mut self, depth: Option<u8>) -> Result<Self, LevelError> {
+        self.paddle_depth = depth.unwrap_or(self.paddle_depth);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Overrides the number of players and validates the level again
+    pub fn with_players(mut self, players: Option<u8>) -> Result<Self, LevelError> {
+        self.players = players.or(self.players);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Overrides the team layout and validates the level again, teams always have 4 players.
+    /// Players set to another number before aren't overridden, they are an error
+    pub fn with_teams(mut self, teams: Option<TeamLayout>) -> Result<Self, LevelError> {
+        if teams.is_some() {
+            match self.players {
+                None | Some(4) => self.players = Some(4),
+                Some(players) => return Err(LevelError::TeamsNeedFourPlayers(players)),
+            }
+        }
+        self.teams = teams.or(self.teams);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Pushed paddles must leave room for the ball in the center of the arena
+    fn max_paddle_depth(&self) -> u8 {
+        let length = if self.player_count() == 4
+            && self.teams.is_none()
+            && self.arena.height < self.arena.width
+        {
+            self.arena.height
+        } else {
+            self.arena.width
+        };
+        (length.saturating_sub(7) / 2).saturating_sub(self.forward_offset())
+    }
+
+    fn player_count(&self) -> u8 {
+        match (self.players, self.teams) {
+            (Some(players), _) => players,
+            (None, Some(_)) => 4,
+            (None, None) => 2,
+        }
+    }
+
+    pub const fn teams(&self) -> Option<TeamLayout> {
+        self.teams
+    }
+
+    /// The distance of the forwards to their wall
+    const fn forward_offset(&self) -> u8 {
+        match self.teams {
+            Some(TeamLayout::Goalie) => 2,
+            _ => 0,
+        }
+    }
+
+    const fn default_paddle_size(&self) -> Odd<u8> {
+        match self.teams {
+            Some(TeamLayout::SideBySide) => Odd::<u8>::new_panics(3),
+            _ => PAD_SIZE,
+        }
+    }
+
+    /// The players taking part in the game
+    pub fn active_players(&self) -> &'static [state::Player] {
+        &state::Player::ALL[..self.player_count().into()]
+    }
+
+    /// The definition, wall and default color of a player,
+    /// the second player of a team gets a lighter shade of the team color
+    fn defaults(&self, player: state::Player) -> (&PlayerDefinition, PlayerSite, Rgb) {
+        let teammate = |color: Rgb| color.mix(Rgb::white(), 0.5);
+        match (player, self.teams) {
+            (state::Player::Player1, _) => (&self.player_1, PlayerSite::Left, Rgb::green()),
+            (state::Player::Player2, _) => (&self.player_2, PlayerSite::Right, Rgb::yellow()),
+            (state::Player::Player3, None) => (&self.player_3, PlayerSite::Top, Rgb::cyan()),
+            (state::Player::Player4, None) => (&self.player_4, PlayerSite::Bottom, Rgb::purple()),
+            (state::Player::Player3, Some(_)) => {
+                (&self.player_3, PlayerSite::Left, teammate(Rgb::green()))
+            }
+            (state::Player::Player4, Some(_)) => {
+                (&self.player_4, PlayerSite::Right, teammate(Rgb::yellow()))
+            }
+        }
+    }
+
+    fn ball_spawn(&self) -> (u8, u8, u8) {
+        self.ball.spawn.unwrap_or(self.arena.center())
+    }
+
+    fn paddle_spawn(&self, player: state::Player) -> (u8, u8) {
+        let (definition, site, _) = self.defaults(player);
+        let (length_y, length_z) = site.plane(&self.arena);
+        let y = match (self.teams, player) {
+            (Some(TeamLayout::SideBySide), state::Player::Player1 | state::Player::Player2) => {
+                length_y / 4
+            }
+            (Some(TeamLayout::SideBySide), _) => length_y - 1 - length_y / 4,
+            (_, _) => length_y / 2,
+        };
+        definition.spawn.unwrap_or((y, length_z / 2))
+    }
+
+    pub fn player(&self, player: state::Player) -> Player {
+        let (definition, site, color) = self.defaults(player);
+        let shape = definition
+            .paddle_shape
+            .clone()
+            .unwrap_or(PaddleShape::Diamond);
+        let size = definition
+            .paddle_size
+            .and_then(Odd::<u8>::new)
+            .unwrap_or(self.default_paddle_size());
+        let offset = match player {
+            state::Player::Player1 | state::Player::Player2 => self.forward_offset(),
+            state::Player::Player3 | state::Player::Player4 => 0,
+        };
+        Player::new(
+            player,
+            site,
+            definition.color.unwrap_or(color),
+            self.arena,
+            (shape, size),
+            self.paddle_spawn(player),
+        )
+        .with_offset(offset)
+        .with_max_depth(self.paddle_depth)
+    }
+
+    pub const fn bounds(&self) -> &Bounds {
+        &self.arena
+    }
+
+    pub fn players(&self) -> Vec<Player> {
+        self.active_players()
+            .iter()
+            .map(|&player| self.player(player))
+            .collect()
+    }
+
+    pub fn ball(&self) -> Ball {
+        Ball::new(
+            self.ball_spawn(),
+            self.ball.color.unwrap_or(Rgb::pink()),
+            self.arena,
+            self.ball.speed,
+        )
+    }
+
+    pub fn obstacles(&self) -> Vec<Obstacle> {
+        self.obstacles
+            .iter()
+            .cloned()
+            .map(|definition| Obstacle::new(definition, self.arena))
+            .collect()
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Bounds {
+            width,
+            height,
+            depth,
+        } = self.arena;
+        write!(
+            f,
+            "{width}x{height}x{depth} arena with {} obstacles",
+            self.obstacles.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::obstacle::{Axis, Motion};
+
+    #[test]
+    fn test_parse() {
+        let level = Level::parse(
+            r##"
+            arena = { width = 16, height = 12, depth = 10 }
+
+            [ball]
+            color = [255, 0, 0]
+            speed = { start_ms = 500, fastest_ms = 200 }
+
+            [player_1]
+            paddle_shape = "cross"
+            paddle_size = 3
+            spawn = [4, 4]
+
+            [player_2]
+            paddle_shape = { custom = ["#.#", ".#.", "#.#"] }
+
+            [[obstacle]]
+            type = "pillar"
+            x = 4
+            z = 2
+
+            [[obstacle]]
+            type = "bar"
+            center = [10, 6, 5]
+            length = 5
+            rotation_ms = 500
+            motion = { axis = "y", distance = 2, step_ms = 200 }
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(level.to_string(), "16x12x10 arena with 2 obstacles");
+        assert_eq!(level.ball().position, (8, 6, 5));
+        assert_eq!(
+            level.player(state::Player::Player1).full_position(),
+            ((0, 1), 4, 4)
+        );
+        assert_eq!(level.player(state::Player::Player1).deltas().len(), 5);
+        assert_eq!(
+            level.player(state::Player::Player2).full_position(),
+            ((15, 14), 6, 5)
+        );
+        assert_eq!(
+            level.player(state::Player::Player2).deltas(),
+            [(-1, -1), (-1, 1), (0, 0), (1, -1), (1, 1)]
+        );
+
+        let level = level
+            .with_paddle(state::Player::Player2, Some(PaddleShape::Square), None)
+            .unwrap();
+        assert_eq!(level.player(state::Player::Player2).deltas().len(), 25);
+        assert!(matches!(
+            Level::default().with_paddle(state::Player::Player1, None, Some(13)),
+            Err(LevelError::PaddleTooLarge { size: 13, .. })
+        ));
+        assert_eq!(
+            Level::default()
+                .with_paddle_depth(Some(6))
+                .unwrap()
+                .player(state::Player::Player1)
+                .max_depth(),
+            6
+        );
+        assert!(matches!(
+            Level::default().with_paddle_depth(Some(7)),
+            Err(LevelError::PaddleDepthTooLarge { depth: 7, max: 6 })
+        ));
+
+        let four = Level::default().with_players(Some(4)).unwrap();
+        assert_eq!(four.players().len(), 4);
+        assert_eq!(
+            four.player(state::Player::Player3).full_position(),
+            ((0, 1), 10, 6)
+        );
+        assert!(four
+            .player(state::Player::Player4)
+            .voxels()
+            .contains(&(10, 18, 6)));
+        assert!(matches!(
+            four.with_paddle_depth(Some(6)),
+            Ok(Level {
+                paddle_depth: 6,
+                ..
+            })
+        ));
+
+        let teams = Level::default()
+            .with_teams(Some(TeamLayout::SideBySide))
+            .unwrap();
+        assert_eq!(teams.players().len(), 4);
+        assert_eq!(
+            teams.player(state::Player::Player3).deltas().len(),
+            5,
+            "Teammates share the wall with smaller paddles"
+        );
+        for spelling in ["side-by-side", "side_by_side"] {
+            let level = Level::parse(&format!("teams = \"{spelling}\"")).unwrap();
+            assert_eq!(level.teams(), Some(TeamLayout::SideBySide));
+            assert_eq!(spelling.parse(), Ok(TeamLayout::SideBySide));
+        }
+        assert!(matches!(
+            Level::parse("teams = \"goalie\"\nplayers = 2"),
+            Err(LevelError::TeamsNeedFourPlayers(2))
+        ));
+        assert!(matches!(
+            Level::default().with_teams(Some(TeamLayout::Goalie)),
+            Ok(Level {
+                players: Some(4),
+                ..
+            })
+        ));
+        assert!(matches!(
+            Level::default()
+                .with_players(Some(2))
+                .and_then(|level| level.with_teams(Some(TeamLayout::Goalie))),
+            Err(LevelError::TeamsNeedFourPlayers(2))
+        ));
+        assert!(Level::default()
+            .with_players(Some(4))
+            .and_then(|level| level.with_teams(Some(TeamLayout::Goalie)))
+            .is_ok());
+        assert!(matches!(
+            level.obstacles[0].shape,
+            Shape::Pillar { x: 4, z: 2 }
+        ));
+        assert!(matches!(
+            level.obstacles[1].motion,
+            Some(Motion {
+                axis: Axis::Y,
+                distance: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Level::parse("[[obstacle]]\ntype = \"cube\""),
+            Err(LevelError::ParseError(_))
+        ));
+        assert!(matches!(
+            Level::parse("[ball]\nsize = 3"),
+            Err(LevelError::ParseError(_))
+        ));
+        for misspelled in [
+            "moton = { axis = \"x\", distance = 2, step_ms = 100 }",
+            "motion = { axes = \"x\", distance = 2, step_ms = 100 }",
+            "colour = [255, 0, 0]",
+            "height = 3",
+        ] {
+            assert!(matches!(
+                Level::parse(&format!(
+                    "[[obstacle]]\ntype = \"pillar\"\nx = 4\nz = 2\n{misspelled}"
+                )),
+                Err(LevelError::ParseError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bundled_levels() {
+        Level::parse(include_str!("../../levels/obstacles.toml")).unwrap();
+        Level::parse(include_str!("../../levels/narrow.toml")).unwrap();
+    }
+
+    #[test]
+    fn test_validation() {
+        let error = |content: &str| Level::parse(content).unwrap_err().to_string();
+
+        assert_eq!(
+            error("arena = { depth = 13 }"),
+            "The arena (20x20x13) doesn't fit into the voxelbox (20x20x12)"
+        );
+        assert_eq!(
+            error("arena = { width = 4 }"),
+            "The arena (4x20x12) is too small, it must be at least 8x3x3"
+        );
+        assert_eq!(
+            error("ball = { speed = { start_ms = 200, fastest_ms = 300 } }"),
+            "The fastest_ms (300) of the ball must be between 1 and its start_ms (200)"
+        );
+        assert_eq!(
+            error("ball = { spawn = [10, 0, 6] }"),
+            "The ball can't spawn at (10, 0, 6), it must be at least 1 voxel away from the walls"
+        );
+        assert_eq!(
+            error("player_2 = { paddle_size = 4 }"),
+            "The paddle of Player 2 must have an odd size, got 4"
+        );
+        assert_eq!(error("players = 3"), "A game has 1, 2 or 4 players, got 3");
+        assert_eq!(
+            error("players = 4\narena = { width = 8 }\nplayer_4 = { paddle_size = 9 }"),
+            "The paddle of Player 4 (size 9) doesn't fit into the width of the arena (8)"
+        );
+        assert_eq!(
+            error("player_1 = { paddle_size = 13 }"),
+            "The paddle of Player 1 (size 13) doesn't fit into the depth of the arena (12)"
+        );
+        assert_eq!(
+            error("player_1 = { paddle_shape = \"square\", paddle_size = 13 }"),
+            "The paddle of Player 1 (size 13) doesn't fit into the depth of the arena (12)"
+        );
+        assert_eq!(
+            error("player_1 = { paddle_shape = { custom = [\"#.\", \"##\"] } }"),
+            "The paddle mask of Player 1 is invalid, the mask must have an odd number of rows and columns, got 2x2"
+        );
+        assert_eq!(
+            error("player_2 = { paddle_shape = { custom = [\"###\"] }, spawn = [10, 0] }"),
+            "The paddle of Player 2 can't spawn at (10, 0), it would stick out of the arena"
+        );
+        assert_eq!(
+            error("player_1 = { spawn = [1, 6] }"),
+            "The paddle of Player 1 can't spawn at (1, 6), it would stick out of the arena"
+        );
+        assert_eq!(
+            error(
+                r#"
+                [[obstacle]]
+                type = "pillar"
+                x = 4
+                z = 2
+
+                [[obstacle]]
+                type = "block"
+                from = [3, 3, 3]
+                to = [4, 4, 4]
+                motion = { axis = "z", distance = 8, step_ms = 100 }
+                "#
+            ),
+            "Obstacle #2 reaches outside of the arena at (3, 3, 12)"
+        );
+        assert_eq!(
+            error("[[obstacle]]\ntype = \"pillar\"\nx = 11\nz = 7"),
+            "Obstacle #1 covers the spawn of the ball"
+        );
+        assert_eq!(
+            error(
+                "[[obstacle]]\ntype = \"pillar\"\nx = 6\nz = 6\n\
+                 motion = { axis = \"x\", distance = 6, step_ms = 100 }"
+            ),
+            "Obstacle #1 covers the spawn of the ball"
+        );
+        assert_eq!(
+            error(
+                "[[obstacle]]\ntype = \"bar\"\ncenter = [10, 10, 3]\nlength = 7\nrotation_ms = 500"
+            ),
+            "Obstacle #1 covers the spawn of the ball"
+        );
+        let moving = |motion: &str| {
+            format!("[[obstacle]]\ntype = \"pillar\"\nx = 4\nz = 2\nmotion = {{ {motion} }}")
+        };
+        assert_eq!(
+            error(&moving("axis = \"x\", distance = 0, step_ms = 100")),
+            "The distance of obstacle #1 must be at least 1"
+        );
+        assert_eq!(
+            error(&moving("axis = \"x\", distance = 2, step_ms = 0")),
+            "The step_ms of obstacle #1 must be at least 1"
+        );
+        assert_eq!(
+            error("[[obstacle]]\ntype = \"bar\"\ncenter = [4, 4, 4]\nlength = 3\nrotation_ms = 0"),
+            "The rotation_ms of obstacle #1 must be at least 1"
+        );
+        assert_eq!(
+            error("[[obstacle]]\ntype = \"wall\"\nx = 4\nhole = [6, 6]\nhole_size = 4"),
+            "The hole in obstacle #1 must have an odd size, got 4"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{color::Rgb, voxelbox::Draw};
+
+use super::player::Player;
+
+/// The remaining lives of a player, shown as every second voxel along the front edge of their wall
+pub struct LivesIndicator<'a> {
+    player: &'a Player,
+    lives: u8,
+}
+
+impl<'a> LivesIndicator<'a> {
+    pub const fn new(player: &'a Player, lives: u8) -> Self {
+        Self { player, lives }
+    }
+}
+
+impl Draw for LivesIndicator<'_> {
+    fn color(&self) -> Rgb {
+        self.player.color()
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        let site = self.player.site();
+        let bounds = self.player.bounds();
+        let (length, _) = site.plane(bounds);
+
+        (0..self.lives)
+            .map(|life| 1 + 2 * life as usize)
+            .take_while(|&a| a < length.into())
+            .map(|a| {
+                let (x, y, z) = site.wall_voxel(bounds, (a as u8, 0));
+                (x.into(), y.into(), z.into())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{level::Level, state};
+
+    #[test]
+    fn test_lives_indicator() {
+        let level = Level::default().with_players(Some(4)).unwrap();
+        let left = level.player(state::Player::Player1);
+        assert_eq!(
+            LivesIndicator::new(&left, 3).draw(),
+            [(0, 1, 0), (0, 3, 0), (0, 5, 0)]
+        );
+
+        let bottom = level.player(state::Player::Player4);
+        assert_eq!(LivesIndicator::new(&bottom, 1).draw(), [(1, 19, 0)]);
+        assert_eq!(LivesIndicator::new(&bottom, 50).draw().len(), 10);
+    }
+}
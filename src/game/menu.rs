@@ -0,0 +1,253 @@
+use crate::{
+    color::Rgb,
+    framework::{Game, Status},
+    input::{Difficulty, Navigation, PlayerInput},
+    voxelbox::{self, Voxelbox, Voxels},
+};
+use std::{num::NonZero, time::Duration};
+
+use super::{
+    level::LevelError,
+    state::{SetRules, WinCondition},
+    GameSettings,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Classic,
+    Multiball,
+    FourPlayers,
+    Practice,
+}
+
+impl Mode {
+    const ALL: [Self; 4] = [
+        Self::Classic,
+        Self::Multiball,
+        Self::FourPlayers,
+        Self::Practice,
+    ];
+
+    const fn color(&self) -> Rgb {
+        match self {
+            Self::Classic => Rgb::green(),
+            Self::Multiball => Rgb::orange(),
+            Self::FourPlayers => Rgb::cyan(),
+            Self::Practice => Rgb::purple(),
+        }
+    }
+}
+
+/// The rows of the menu, the D-pad or stick moves between them up and down
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Row {
+    Mode,
+    WinningPoints,
+}
+
+/// Picks the mode and the winning points of the next match on the cube.
+/// Up and down select a row, left and right change its value and the confirm button starts the match
+pub struct Menu {
+    mode: usize,
+    winning_points: NonZero<u8>,
+    row: Row,
+    confirmed: bool,
+}
+
+impl Menu {
+    const MAX_WINNING_POINTS: u8 = 15;
+    /// The number of paddle hits after which another ball joins in the multiball mode
+    const MULTIBALL_HITS: NonZero<u8> = NonZero::new(4).unwrap();
+
+    pub const fn new(winning_points: NonZero<u8>) -> Self {
+        Self {
+            mode: 0,
+            winning_points,
+            row: Row::Mode,
+            confirmed: false,
+        }
+    }
+
+    pub const fn mode(&self) -> Mode {
+        Mode::ALL[self.mode]
+    }
+
+    pub fn navigate(&mut self, navigation: Navigation) {
+        let step: i8 = match navigation {
+            Navigation::Up => {
+                self.row = Row::Mode;
+                return;
+            }
+            Navigation::Down => {
+                self.row = Row::WinningPoints;
+                return;
+            }
+            Navigation::Confirm => {
+                self.confirmed = true;
+                return;
+            }
+            Navigation::Left => -1,
+            Navigation::Right => 1,
+        };
+
+        match self.row {
+            Row::Mode => {
+                self.mode = (self.mode as i8 + step).rem_euclid(Mode::ALL.len() as i8) as usize;
+            }
+            Row::WinningPoints => {
+                let points = (self.winning_points.get() as i8 + step)
+                    .clamp(1, Self::MAX_WINNING_POINTS as i8);
+                self.winning_points = NonZero::new(points as u8).unwrap_or(NonZero::<u8>::MIN);
+            }
+        }
+    }
+
+    /// The settings of the selected match, players without a controller are played by bots
+    pub fn apply(
+        &self,
+        settings: &GameSettings,
+        controllers: usize,
+    ) -> Result<GameSettings, LevelError> {
+        let mut settings = settings.clone();
+        settings.win_condition = WinCondition::Points(self.winning_points);
+        settings.set_rules = SetRules::default();
+        settings.multiball.paddle_hits = None;
+        settings.multiball.interval = None;
+
+        let players = match self.mode() {
+            Mode::Classic => 2,
+            Mode::Multiball => {
+                settings.multiball.paddle_hits = Some(Self::MULTIBALL_HITS);
+                2
+            }
+            Mode::FourPlayers => 4,
+            Mode::Practice => 1,
+        };
+        settings.level = settings.level.with_players(Some(players))?;
+        // Two players share a gamepad, more players need an own one
+        let humans = if players == 2 { 2 } else { controllers };
+        for player in settings.players.iter_mut().skip(humans) {
+            player.bot.get_or_insert(Difficulty::Medium);
+        }
+        Ok(settings)
+    }
+}
+
+impl Game for Menu {
+    fn update(&mut self, _dt: Duration, inputs: &mut [PlayerInput]) -> Status {
+        for input in inputs {
+            for navigation in input.take_navigation() {
+                self.navigate(navigation);
+            }
+        }
+        // The menu keeps the selection for the next match
+        if std::mem::take(&mut self.confirmed) {
+            Status::Finished
+        } else {
+            Status::Running
+        }
+    }
+
+    fn render(&self, voxelbox: &mut Voxelbox) {
+        let dimmed = |color: Rgb, row: Row| {
+            if row == self.row {
+                color
+            } else {
+                color.mix(Rgb::black(), 0.6)
+            }
+        };
+
+        for (index, mode) in Mode::ALL.iter().enumerate() {
+            let selected = index == self.mode;
+            let color = if selected {
+                mode.color()
+            } else {
+                mode.color().mix(Rgb::black(), 0.85)
+            };
+            voxelbox.draw(&Voxels {
+                voxels: square(1 + 5 * index, 4, if selected { 3 } else { 2 }),
+                color: dimmed(color, Row::Mode),
+            });
+        }
+
+        voxelbox.draw(&Voxels {
+            voxels: (0..self.winning_points.get() as usize)
+                .map(|point| (1 + point, 13, BACK))
+                .collect(),
+            color: dimmed(Rgb::white(), Row::WinningPoints),
+        });
+    }
+}
+
+/// The layer at the back of the cube the menu is shown on
+const BACK: usize = voxelbox::DEEPTH as usize - 1;
+
+/// A square of `size` voxels on the back of the cube starting at `(x, y)`
+fn square(x: usize, y: usize, size: usize) -> Vec<(usize, usize, usize)> {
+    (x..x + size)
+        .flat_map(|x| (y..y + size).map(move |y| (x, y, BACK)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{input::ControlMode, PlayerSettings},
+        game::{level::Level, multiball::MultiballSettings, powerup::PowerUpSettings},
+        positive::Positive,
+    };
+
+    #[test]
+    fn test_menu() {
+        let mut menu = Menu::new(NonZero::new(5).unwrap());
+        for navigation in [Navigation::Left, Navigation::Left] {
+            menu.navigate(navigation);
+        }
+        assert_eq!(menu.mode(), Mode::FourPlayers);
+        for navigation in [Navigation::Down, Navigation::Right, Navigation::Up] {
+            menu.navigate(navigation);
+        }
+        assert_eq!(menu.winning_points.get(), 6);
+        assert_eq!(menu.update(Duration::ZERO, &mut []), Status::Running);
+        menu.navigate(Navigation::Confirm);
+        assert_eq!(menu.update(Duration::ZERO, &mut []), Status::Finished);
+        assert_eq!(menu.update(Duration::ZERO, &mut []), Status::Running);
+
+        let player = PlayerSettings {
+            sensitivity: Positive::new(1.0).unwrap(),
+            control: ControlMode::Velocity,
+            bot: None,
+        };
+        let settings = GameSettings {
+            players: [player.clone(), player.clone(), player.clone(), player],
+            connectivity: ("127.0.0.1".to_string(), 0),
+            win_condition: WinCondition::Points(NonZero::new(5).unwrap()),
+            set_rules: SetRules::default(),
+            multiball: MultiballSettings {
+                paddle_hits: None,
+                interval: None,
+                max_balls: NonZero::new(4).unwrap(),
+            },
+            powerups: PowerUpSettings {
+                interval: None,
+                duration: Duration::from_secs(1),
+            },
+            level: Level::default(),
+        };
+        let four_players = menu.apply(&settings, 2).unwrap();
+        assert_eq!(four_players.level.active_players().len(), 4);
+        assert_eq!(
+            four_players.players.map(|player| player.bot.is_some()),
+            [false, false, true, true]
+        );
+        assert_eq!(
+            four_players.win_condition,
+            WinCondition::Points(NonZero::new(6).unwrap())
+        );
+
+        menu.navigate(Navigation::Right);
+        let practice = menu.apply(&settings, 1).unwrap();
+        assert_eq!(practice.level.active_players().len(), 1);
+    }
+}
@@ -1,103 +1,415 @@
-use crate::{input::JoyStick, log::Severity, positive::Positive, prelude::*, voxelbox};
-use ball_movement::{handle_ball_movement_and_score, update_game_state_and_reset};
+use crate::{
+    framework::{self, Game, Status},
+    input::{Bot, Controllers, Difficulty, JoyStick, KeyboardLayout, Navigation, PlayerInput},
+    log,
+    positive::Positive,
+    voxelbox::Voxelbox,
+};
+use ball::Ball;
+use ball_movement::{handle_ball_movement_and_score, update_game_state_and_reset, Goal};
 use gilrs::Gilrs;
-use input::{handle_player_input, PlayerMovementTimestamps};
+use input::{handle_players_input, ControlMode, PlayerMotion};
+use level::Level;
+use lives::LivesIndicator;
+use menu::Menu;
+use multiball::{Multiball, MultiballSettings};
+use obstacle::Obstacle;
+use player::Player;
+use powerup::{PowerUpSettings, PowerUps};
+use practice::{end_run_and_reset, Practice, ScoreBar};
 use std::{
     num::NonZero,
-    sync::LazyLock,
     time::{Duration, Instant},
 };
+use time_bar::TimeBar;
 
 pub mod ball;
 pub mod ball_movement;
 pub mod collision;
+pub mod effect;
 pub mod input;
+pub mod level;
+pub mod lives;
+pub mod menu;
+pub mod multiball;
+pub mod obstacle;
+pub mod paddle;
 pub mod player;
+pub mod powerup;
+pub mod practice;
+#[cfg(test)]
+mod scenario;
+pub mod simulation;
 pub mod state;
+pub mod time_bar;
+
+#[derive(Debug, Clone)]
+pub struct PlayerSettings {
+    /// Controls the paddle speed
+    pub sensitivity: Positive<f32>,
+    pub control: ControlMode,
+    /// The player is controlled by the computer with this difficulty
+    pub bot: Option<Difficulty>,
+}
 
-const FPS: f32 = 10.0;
-static RENDER_FRAME_DURATION: LazyLock<Duration> =
-    LazyLock::new(|| Duration::from_secs_f32(1.0 / FPS));
+#[derive(Clone)]
+pub struct GameSettings {
+    /// The settings of player 1 to 4, only the players of the level are used
+    pub players: [PlayerSettings; 4],
+    pub connectivity: (String, u16),
+    pub win_condition: state::WinCondition,
+    pub set_rules: state::SetRules,
+    pub multiball: MultiballSettings,
+    pub powerups: PowerUpSettings,
+    pub level: Level,
+}
 
-struct MovementTimestamps {
-    player_1: PlayerMovementTimestamps,
-    player_2: PlayerMovementTimestamps,
-    ball: Instant,
-    render: Instant,
+/// The controllers are handed to the players who aren't bots in order.
+/// Player 1 and 2 share the first gamepad if there is only one, every other player needs an own one.
+/// On the keyboard the first player uses WASD and every other one the arrow keys,
+/// over the network every player has an own remote controller
+fn inputs(controllers: Controllers, settings: &[PlayerSettings]) -> Vec<PlayerInput> {
+    let humans = settings
+        .iter()
+        .filter(|player| player.bot.is_none())
+        .count();
+    let shared = controllers.len() < humans;
+    let mut human = 0;
+    settings
+        .iter()
+        .enumerate()
+        .map(|(index, player)| {
+            if let Some(difficulty) = player.bot {
+                return PlayerInput::Bot(Bot::new(difficulty));
+            }
+            let own = human;
+            human += 1;
+            let gamepads = match controllers {
+                Controllers::Gamepads(gamepads) => gamepads,
+                // Player 2 and 4 face the other way, like on the gamepads
+                Controllers::Keyboard(terminal) => {
+                    let layout = match own {
+                        0 => KeyboardLayout::Wasd,
+                        _ => KeyboardLayout::Arrows,
+                    };
+                    return PlayerInput::Keyboard(terminal.keyboard(layout, index % 2 == 1));
+                }
+                Controllers::Network(server) => {
+                    return PlayerInput::Remote(server.remote(own, index % 2 == 1));
+                }
+            };
+            let id = gamepads.get(own).copied().unwrap_or(gamepads[0]);
+            let sensitivity = player.sensitivity.clone();
+            PlayerInput::JoyStick(match index {
+                0 => JoyStick::new_player_1(id, sensitivity),
+                1 => JoyStick::new_player_2(id, sensitivity, !shared),
+                2 => JoyStick::new_player_3(id, sensitivity),
+                _ => JoyStick::new_player_4(id, sensitivity),
+            })
+        })
+        .collect()
 }
 
-impl Default for MovementTimestamps {
-    fn default() -> Self {
+/// What happened during a single update of [`Pong`]
+pub struct Step {
+    pub status: Status,
+    pub goal: Option<Goal>,
+    pub paddle_hits: u8,
+}
+
+/// Classic 3D Pong and all of its modes
+pub struct Pong {
+    level: Level,
+    state: state::GameState,
+    players: Vec<Player>,
+    /// The motion of player 1 to 4, indexed by the id of the player
+    motions: Vec<PlayerMotion>,
+    balls: Vec<(Ball, Instant)>,
+    multiball: Multiball,
+    obstacles: Vec<Obstacle>,
+    powerups: PowerUps,
+    practice: Option<Practice>,
+    /// Confirming on a controller ends a practice, otherwise practice runs never end
+    confirm_ends_practice: bool,
+    sudden_death: bool,
+    /// The time of the game, advanced by every update
+    now: Instant,
+}
+
+impl Pong {
+    pub fn new(settings: GameSettings) -> Self {
+        let level = settings.level;
+        let player_settings = &settings.players[..level.active_players().len()];
+        let state = state::GameState::new(
+            level.active_players().len(),
+            level.teams().is_some(),
+            settings.win_condition,
+        )
+        .with_rules(settings.set_rules);
+        let players = level.players();
+        let now = Instant::now();
+
         Self {
-            player_1: PlayerMovementTimestamps::default(),
-            player_2: PlayerMovementTimestamps::default(),
-            ball: Instant::now(),
-            render: Instant::now(),
+            state,
+            // Bots always control the velocity of their paddle
+            motions: player_settings
+                .iter()
+                .map(|player| match player.bot {
+                    Some(_) => PlayerMotion::new(ControlMode::Velocity),
+                    None => PlayerMotion::new(player.control),
+                })
+                .collect(),
+            balls: vec![(level.ball(), now)],
+            multiball: Multiball::new(settings.multiball),
+            obstacles: level.obstacles(),
+            powerups: PowerUps::new(settings.powerups),
+            practice: (players.len() == 1).then(Practice::default),
+            confirm_ends_practice: false,
+            sudden_death: false,
+            now,
+            players,
+            level,
+        }
+    }
+
+    /// Lets the players leave a practice with confirm, e.g. to get back to the menu
+    pub const fn with_confirm_ending_practice(mut self) -> Self {
+        self.confirm_ends_practice = true;
+        self
+    }
+
+    /// Advances the game by `dt` like [`Game::update`] and reports the goal and the paddle hits
+    pub fn step(&mut self, dt: Duration, inputs: &mut [PlayerInput]) -> Step {
+        self.now += dt;
+        let now = self.now;
+
+        if let (Some(practice), true) = (&self.practice, self.confirm_ends_practice) {
+            let confirmed = inputs
+                .iter_mut()
+                .any(|input| input.take_navigation().contains(&Navigation::Confirm));
+            if confirmed {
+                log!(
+                    Success,
+                    "Practice ended with a high score of {} returns",
+                    practice.high_score().max(practice.returns())
+                );
+                return Step {
+                    status: Status::Finished,
+                    goal: None,
+                    paddle_hits: 0,
+                };
+            }
+        }
+
+        for player in &self.players {
+            let input = &mut inputs[player.id().index()];
+            input.observe(player, self.balls.iter().map(|(ball, _)| ball), now);
+        }
+        handle_players_input(inputs, &mut self.players, &mut self.motions, now);
+        for obstacle in &mut self.obstacles {
+            obstacle.update(now);
+        }
+        for player in &mut self.players {
+            player.update_effects(now);
+        }
+        for (ball, _) in &mut self.balls {
+            ball.update_effects(now);
+        }
+
+        let (goal, paddle_hits) =
+            handle_ball_movement_and_score(&mut self.balls, &self.players, &self.obstacles, now);
+        if let Some(goal) = &goal {
+            let ball;
+            (self.players, ball) = match &mut self.practice {
+                Some(practice) => end_run_and_reset(practice, &self.level),
+                None => update_game_state_and_reset(goal, &mut self.state, &self.level),
+            };
+            self.balls = vec![(ball, now)];
+            self.multiball.reset(now);
+            self.powerups.reset(now);
+        } else {
+            if let Some(practice) = &mut self.practice {
+                practice.add_returns(paddle_hits);
+            }
+            self.multiball
+                .spawn(&mut self.balls, paddle_hits, &self.level, now);
+            self.powerups.update(
+                now,
+                &mut self.balls,
+                &mut self.players,
+                &self.multiball,
+                &self.level,
+                &self.obstacles,
+            );
+        }
+
+        // Practice runs only end on confirm, the win condition only applies to matches
+        let mut status = Status::Running;
+        if self.practice.is_none() {
+            if let Some(winner) = self.state.winner(now) {
+                log!(Success, "{} won ({})", winner, self.state.fmt_score());
+                status = Status::Finished;
+            } else if !self.sudden_death && self.state.is_sudden_death(now) {
+                self.sudden_death = true;
+                log!(
+                    Warning,
+                    "Time is up at {}, sudden death until someone leads",
+                    self.state.fmt_score()
+                );
+            }
+        }
+        Step {
+            status,
+            goal,
+            paddle_hits,
         }
     }
 }
 
-pub fn game_loop(
-    player_1_sensitivity: Positive<f32>,
-    player_2_sensitivity: Positive<f32>,
-    connectivity: (String, u16),
-    gilrs: &mut Gilrs,
-    gamepad_id: (gilrs::GamepadId, Option<gilrs::GamepadId>),
-    winning_points: NonZero<u8>,
-) {
-    let mut last_movements = MovementTimestamps::default();
-    let mut state = state::GameState::default();
+impl Game for Pong {
+    fn update(&mut self, dt: Duration, inputs: &mut [PlayerInput]) -> Status {
+        self.step(dt, inputs).status
+    }
 
-    let mut voxelbox = voxelbox::Voxelbox::new(connectivity.0, connectivity.1);
-    let mut player_1 = player::Player::player_1();
-    let mut player_2 = player::Player::player_2();
-    let mut ball = ball::Ball::default();
+    fn render(&self, voxelbox: &mut Voxelbox) {
+        let bounds = self.level.bounds();
+        for obstacle in &self.obstacles {
+            voxelbox.draw(obstacle);
+        }
+        for powerup in self.powerups.items() {
+            voxelbox.draw(powerup);
+        }
+        if let Some(practice) = &self.practice {
+            voxelbox.draw(&ScoreBar::high_score(bounds, practice));
+            voxelbox.draw(&ScoreBar::run(bounds, practice, &self.players[0]));
+        } else {
+            if let Some(remaining) = self.state.remaining_share(self.now) {
+                voxelbox.draw(&TimeBar::new(bounds, remaining, self.sudden_death));
+            }
+            for player in &self.players {
+                if let Some(lives) = self.state.lives(&player.id()) {
+                    voxelbox.draw(&LivesIndicator::new(player, lives));
+                }
+            }
+        }
+        for player in &self.players {
+            voxelbox.draw(player);
+        }
+        for (ball, _) in &self.balls {
+            voxelbox.draw(ball);
+        }
+    }
+}
+
+pub fn game_loop(settings: GameSettings, gilrs: &mut Gilrs, controllers: Controllers) {
+    let (ip, port) = settings.connectivity.clone();
+    let mut voxelbox = Voxelbox::new(ip, port);
+    play(settings, gilrs, controllers, &mut voxelbox, false);
+}
 
-    let mut player_1_joystick = JoyStick::new_player_1(gamepad_id.0, player_1_sensitivity);
-    let mut player_2_joystick = JoyStick::new_player_2(
-        gamepad_id.1.unwrap_or(gamepad_id.0),
-        player_2_sensitivity,
-        gamepad_id.1.is_some(),
-    );
+/// Shows the menu on the cube before every match, every controller can navigate it
+pub fn menu_loop(settings: GameSettings, gilrs: &mut Gilrs, controllers: Controllers) {
+    let (ip, port) = settings.connectivity.clone();
+    let mut voxelbox = Voxelbox::new(ip, port);
+    let mut navigation: Vec<_> = match controllers {
+        Controllers::Gamepads(gamepads) => gamepads
+            .iter()
+            .map(|&id| {
+                let sensitivity = settings.players[0].sensitivity.clone();
+                PlayerInput::JoyStick(JoyStick::new_player_1(id, sensitivity))
+            })
+            .collect(),
+        Controllers::Keyboard(terminal) => [KeyboardLayout::Wasd, KeyboardLayout::Arrows]
+            .map(|layout| PlayerInput::Keyboard(terminal.keyboard(layout, false)))
+            .into(),
+        // Remote controllers can't navigate, the menu waits for the confirmation forever
+        Controllers::Network(_) => Vec::new(),
+    };
+    let winning_points = match settings.win_condition {
+        state::WinCondition::Points(winning_points) => winning_points,
+        _ => NonZero::new(5).unwrap(),
+    };
+    let mut menu = Menu::new(winning_points);
 
     loop {
-        if let Some(event) = gilrs.next_event() {
-            player_1_joystick.add_event(&event);
-            player_2_joystick.add_event(&event);
-        }
-
-        handle_player_input(
-            &player_1_joystick,
-            &mut player_1,
-            &mut last_movements.player_1,
-        );
-        handle_player_input(
-            &player_2_joystick,
-            &mut player_2,
-            &mut last_movements.player_2,
-        );
-        let scoring_player = handle_ball_movement_and_score(
-            &mut ball,
-            &player_1,
-            &player_2,
-            &mut last_movements.ball,
-        );
-        if let Some(p) = scoring_player {
-            (player_1, player_2, ball) =
-                update_game_state_and_reset(&p, &mut state, winning_points);
+        framework::run(&mut menu, &mut navigation, gilrs, &mut voxelbox);
+        match menu.apply(&settings, controllers.len()) {
+            Ok(settings) => {
+                log!(Log, "Starting {:?}", menu.mode());
+                play(settings, gilrs, controllers, &mut voxelbox, true);
+            }
+            Err(err) => log!(Critical, "{}", err),
         }
+    }
+}
 
-        let now = Instant::now();
-        let duration_since_last_render = now - last_movements.render;
-        if duration_since_last_render >= *RENDER_FRAME_DURATION {
-            voxelbox.reset_leds();
-            voxelbox.draw(&player_1);
-            voxelbox.draw(&player_2);
-            voxelbox.draw(&ball);
-            voxelbox
-                .send()
-                .log(Severity::Warning, "Could not send pixel-data to Voxelbox");
-            last_movements.render = now;
+/// Plays a single match, a practice started from the menu ends on confirm
+fn play(
+    settings: GameSettings,
+    gilrs: &mut Gilrs,
+    controllers: Controllers,
+    voxelbox: &mut Voxelbox,
+    from_menu: bool,
+) {
+    let players = settings.level.active_players().len();
+    let mut inputs = inputs(controllers, &settings.players[..players]);
+    let mut pong = Pong::new(settings);
+    if from_menu {
+        pong = pong.with_confirm_ending_practice();
+        if players == 1 {
+            log!(
+                Log,
+                "Press confirm to end the practice and get back to the menu"
+            );
         }
     }
+
+    framework::run(&mut pong, &mut inputs, gilrs, voxelbox);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZero;
+
+    #[test]
+    fn test_pong() {
+        crate::random::seed(0x9046);
+        let bot = PlayerSettings {
+            sensitivity: Positive::new(1.0).unwrap(),
+            control: ControlMode::Absolute,
+            bot: Some(Difficulty::Hard),
+        };
+        let easy_bot = PlayerSettings {
+            bot: Some(Difficulty::Easy),
+            ..bot.clone()
+        };
+        let mut pong = Pong::new(GameSettings {
+            players: [bot.clone(), easy_bot, bot.clone(), bot],
+            connectivity: ("127.0.0.1".to_string(), 0),
+            win_condition: state::WinCondition::Points(NonZero::new(1).unwrap()),
+            set_rules: state::SetRules::default(),
+            multiball: MultiballSettings {
+                paddle_hits: None,
+                interval: None,
+                max_balls: NonZero::new(1).unwrap(),
+            },
+            powerups: PowerUpSettings {
+                interval: None,
+                duration: Duration::from_secs(1),
+            },
+            level: Level::default(),
+        });
+        let mut inputs = [Difficulty::Hard, Difficulty::Easy]
+            .map(|difficulty| PlayerInput::Bot(Bot::new(difficulty)));
+
+        let mut voxelbox = Voxelbox::new("127.0.0.1", 0);
+        let updates = (0..100_000)
+            .take_while(|_| pong.update(Duration::from_millis(10), &mut inputs) == Status::Running)
+            .count();
+        assert!(updates < 100_000, "A match up to 1 point ends");
+        assert!(["1:0", "0:1"].contains(&pong.state.fmt_score().as_str()));
+
+        pong.render(&mut voxelbox);
+    }
 }
@@ -0,0 +1,117 @@
+use std::{
+    num::NonZero,
+    time::{Duration, Instant},
+};
+
+use crate::{color::Rgb, log};
+
+use super::{ball::Ball, level::Level};
+
+const BALL_COLORS: [Rgb; 5] = [
+    Rgb::pink(),
+    Rgb::cyan(),
+    Rgb::orange(),
+    Rgb::purple(),
+    Rgb::white(),
+];
+
+#[derive(Debug, Clone)]
+pub struct MultiballSettings {
+    /// Spawn another ball after this many paddle hits
+    pub paddle_hits: Option<NonZero<u8>>,
+    /// Spawn another ball after this duration passed
+    pub interval: Option<Duration>,
+    pub max_balls: NonZero<u8>,
+}
+
+pub struct Multiball {
+    settings: MultiballSettings,
+    paddle_hits: u8,
+    last_spawn: Instant,
+}
+
+impl Multiball {
+    pub fn new(settings: MultiballSettings) -> Self {
+        Self {
+            settings,
+            paddle_hits: 0,
+            last_spawn: Instant::now(),
+        }
+    }
+
+    pub fn reset(&mut self, now: Instant) {
+        self.paddle_hits = 0;
+        self.last_spawn = now;
+    }
+
+    /// Adds a new ball to `balls` once enough paddle hits happened or the interval elapsed
+    pub fn spawn(
+        &mut self,
+        balls: &mut Vec<(Ball, Instant)>,
+        paddle_hits: u8,
+        level: &Level,
+        now: Instant,
+    ) {
+        self.paddle_hits = self.paddle_hits.saturating_add(paddle_hits);
+
+        // At the cap no ball is added, the hits and the interval keep counting until a goal resets them
+        if balls.len() >= self.settings.max_balls.get().into() {
+            return;
+        }
+
+        let hits_reached = self
+            .settings
+            .paddle_hits
+            .is_some_and(|hits| self.paddle_hits >= hits.get());
+        let interval_elapsed = self
+            .settings
+            .interval
+            .is_some_and(|interval| now.duration_since(self.last_spawn) >= interval);
+
+        if (hits_reached || interval_elapsed) && self.add_ball(balls, level, now) {
+            self.reset(now);
+        }
+    }
+
+    /// Adds a ball in the next color unless the maximum number of balls is reached
+    pub fn add_ball(&self, balls: &mut Vec<(Ball, Instant)>, level: &Level, now: Instant) -> bool {
+        if balls.len() >= self.settings.max_balls.get().into() {
+            return false;
+        }
+
+        let color = BALL_COLORS[balls.len() % BALL_COLORS.len()];
+        balls.push((level.ball().randomized().with_color(color), now));
+        log!(Log, "Another ball joined the game ({} balls)", balls.len());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_at_cap() {
+        let level = Level::default();
+        let mut multiball = Multiball::new(MultiballSettings {
+            paddle_hits: None,
+            interval: Some(Duration::from_secs(1)),
+            max_balls: NonZero::new(2).unwrap(),
+        });
+        let start = multiball.last_spawn;
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut balls = vec![(level.ball(), start)];
+
+        multiball.spawn(&mut balls, 0, &level, at(500));
+        assert_eq!(balls.len(), 1);
+        multiball.spawn(&mut balls, 0, &level, at(1000));
+        assert_eq!(balls.len(), 2);
+
+        // At the cap no ball joins, a lost ball is replaced once the interval elapsed
+        multiball.spawn(&mut balls, 0, &level, at(2500));
+        assert_eq!(balls.len(), 2);
+        balls.pop();
+        multiball.spawn(&mut balls, 0, &level, at(2600));
+        assert_eq!(balls.len(), 2);
+    }
+}
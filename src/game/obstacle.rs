@@ -0,0 +1,216 @@
+use serde::{de::Error, Deserialize, Deserializer};
+use std::time::{Duration, Instant};
+
+use super::collision::Bounds;
+use crate::{color::Rgb, voxelbox::Draw};
+
+/// The directions a rotating bar can point to in the Y/Z plane
+const BAR_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (1, 1), (0, 1), (-1, 1)];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Shape {
+    /// A cuboid spanning from `from` to `to`, both are inclusive
+    Block {
+        from: (u8, u8, u8),
+        to: (u8, u8, u8),
+    },
+    /// A column spanning the whole height of the box
+    Pillar { x: u8, z: u8 },
+    /// A wall covering the whole Y/Z plane at `x` with a square hole centered at `hole`,
+    /// its sides are `hole_size` voxels long and odd
+    Wall {
+        x: u8,
+        hole: (u8, u8),
+        hole_size: u8,
+    },
+    /// A bar in the Y/Z plane rotating around its center
+    Bar {
+        center: (u8, u8, u8),
+        length: u8,
+        rotation_ms: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Moves the obstacle back and forth along an axis
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Motion {
+    pub axis: Axis,
+    pub distance: u8,
+    pub step_ms: u64,
+}
+
+impl Shape {
+    /// The voxels of the shape, they may be outside of the arena
+    pub fn voxels(&self, bounds: &Bounds, rotation: usize) -> Vec<(i16, i16, i16)> {
+        match *self {
+            Self::Block { from, to } => cuboid(from, to),
+            Self::Pillar { x, z } => cuboid((x, 0, z), (x, bounds.height - 1, z)),
+            Self::Wall { x, hole, hole_size } => {
+                let half = (hole_size / 2) as i16;
+                let in_hole = |value: i16, center: u8| (value - center as i16).abs() <= half;
+                cuboid((x, 0, 0), (x, bounds.height - 1, bounds.depth - 1))
+                    .into_iter()
+                    .filter(|&(_, y, z)| {
+                        hole_size == 0 || !(in_hole(y, hole.0) && in_hole(z, hole.1))
+                    })
+                    .collect()
+            }
+            Self::Bar { center, length, .. } => {
+                let (x, y, z) = center;
+                let (dy, dz) = BAR_DIRECTIONS[rotation % BAR_DIRECTIONS.len()];
+                let half = (length / 2) as i16;
+                (-half..=half)
+                    .map(|i| (x as i16, y as i16 + i * dy as i16, z as i16 + i * dz as i16))
+                    .collect()
+            }
+        }
+    }
+
+    /// The number of different rotations the shape can have
+    pub const fn rotations(&self) -> usize {
+        match self {
+            Self::Bar { .. } => BAR_DIRECTIONS.len(),
+            _ => 1,
+        }
+    }
+}
+
+impl Motion {
+    pub const fn shift(&self, (x, y, z): (i16, i16, i16), offset: u8) -> (i16, i16, i16) {
+        let offset = offset as i16;
+        match self.axis {
+            Axis::X => (x + offset, y, z),
+            Axis::Y => (x, y + offset, z),
+            Axis::Z => (x, y, z + offset),
+        }
+    }
+}
+
+/// An obstacle of a level, the fields of its shape are next to `motion` and `color`
+#[derive(Debug, Clone)]
+pub struct ObstacleDefinition {
+    pub shape: Shape,
+    pub motion: Option<Motion>,
+    pub color: Option<Rgb>,
+}
+
+/// Flattening the shape with `#[serde(flatten)]` would silently drop unknown fields,
+/// so the shared fields are taken out first and every remaining field has to belong to the shape
+impl<'de> Deserialize<'de> for ObstacleDefinition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut table = toml::Table::deserialize(deserializer)?;
+        let motion = table.remove("motion").map(toml::Value::try_into);
+        let color = table.remove("color").map(toml::Value::try_into);
+        Ok(Self {
+            shape: toml::Value::Table(table)
+                .try_into()
+                .map_err(D::Error::custom)?,
+            motion: motion.transpose().map_err(D::Error::custom)?,
+            color: color.transpose().map_err(D::Error::custom)?,
+        })
+    }
+}
+
+pub struct Obstacle {
+    shape: Shape,
+    motion: Option<Motion>,
+    color: Rgb,
+    bounds: Bounds,
+    offset: u8,
+    moving_forward: bool,
+    rotation: usize,
+    last_motion: Instant,
+    last_rotation: Instant,
+}
+
+impl Obstacle {
+    pub fn new(definition: ObstacleDefinition, bounds: Bounds) -> Self {
+        let now = Instant::now();
+        Self {
+            shape: definition.shape,
+            motion: definition.motion,
+            color: definition.color.unwrap_or(Rgb::gray()),
+            bounds,
+            offset: 0,
+            moving_forward: true,
+            rotation: 0,
+            last_motion: now,
+            last_rotation: now,
+        }
+    }
+
+    /// Advances moving and rotating obstacles
+    pub fn update(&mut self, now: Instant) {
+        if let Some(motion) = &self.motion {
+            if now.duration_since(self.last_motion) >= Duration::from_millis(motion.step_ms) {
+                self.last_motion = now;
+                if self.offset >= motion.distance {
+                    self.moving_forward = false;
+                } else if self.offset == 0 {
+                    self.moving_forward = true;
+                }
+
+                if self.moving_forward {
+                    self.offset += 1;
+                } else {
+                    self.offset = self.offset.saturating_sub(1);
+                }
+            }
+        }
+
+        if let Shape::Bar { rotation_ms, .. } = self.shape {
+            if now.duration_since(self.last_rotation) >= Duration::from_millis(rotation_ms) {
+                self.last_rotation = now;
+                self.rotation = (self.rotation + 1) % BAR_DIRECTIONS.len();
+            }
+        }
+    }
+
+    /// Every voxel occupied by the obstacle, voxels outside of the arena are skipped
+    pub fn voxels(&self) -> Vec<(u8, u8, u8)> {
+        self.shape
+            .voxels(&self.bounds, self.rotation)
+            .into_iter()
+            .map(|voxel| match &self.motion {
+                Some(motion) => motion.shift(voxel, self.offset),
+                None => voxel,
+            })
+            .filter(|&voxel| self.bounds.contains(voxel))
+            .map(|(x, y, z)| (x as u8, y as u8, z as u8))
+            .collect()
+    }
+}
+
+fn cuboid(from: (u8, u8, u8), to: (u8, u8, u8)) -> Vec<(i16, i16, i16)> {
+    let (x1, x2) = (from.0.min(to.0), from.0.max(to.0));
+    let (y1, y2) = (from.1.min(to.1), from.1.max(to.1));
+    let (z1, z2) = (from.2.min(to.2), from.2.max(to.2));
+
+    (x1..=x2)
+        .flat_map(|x| (y1..=y2).flat_map(move |y| (z1..=z2).map(move |z| (x, y, z))))
+        .map(|(x, y, z)| (x.into(), y.into(), z.into()))
+        .collect()
+}
+
+impl Draw for Obstacle {
+    fn color(&self) -> Rgb {
+        self.color
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        self.voxels()
+            .into_iter()
+            .map(|(x, y, z)| (x.into(), y.into(), z.into()))
+            .collect()
+    }
+}
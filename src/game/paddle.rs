@@ -0,0 +1,202 @@
+use serde::Deserialize;
+use std::fmt;
+
+use crate::odd::Odd;
+
+pub const PAD_SIZE: Odd<u8> = Odd::<u8>::new_panics(5);
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaddleShape {
+    Diamond,
+    Square,
+    Circle,
+    Cross,
+    /// The rows of a mask from top to bottom, `#` marks a voxel and `.` an empty one.
+    /// The mask is centered on the paddle and ignores the size
+    Custom(Vec<String>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MaskError {
+    Empty,
+    UnevenRows,
+    EvenDimensions { rows: usize, columns: usize },
+    TooLarge { rows: usize, columns: usize },
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("the mask has no voxels"),
+            Self::UnevenRows => f.write_str("all rows of the mask must have the same length"),
+            Self::EvenDimensions { rows, columns } => write!(
+                f,
+                "the mask must have an odd number of rows and columns, got {rows}x{columns}"
+            ),
+            Self::TooLarge { rows, columns } => write!(
+                f,
+                "the mask may have at most {} rows and columns, got {rows}x{columns}",
+                PaddleShape::MAX_MASK_LENGTH
+            ),
+            Self::InvalidCharacter(c) => {
+                write!(f, "the mask may only contain '#' and '.', got '{c}'")
+            }
+        }
+    }
+}
+
+impl PaddleShape {
+    /// The offsets of the voxels of a mask around its center have to fit into an `i8`
+    const MAX_MASK_LENGTH: usize = i8::MAX as usize;
+
+    /// The offsets `(y, z)` of every voxel of the paddle around its center
+    pub fn deltas(&self, size: Odd<u8>) -> Vec<(i8, i8)> {
+        let padding = (size.value() as i8 - 1) / 2;
+        let square = (-padding..=padding).flat_map(|y| (-padding..=padding).map(move |z| (y, z)));
+
+        match self {
+            Self::Diamond => square
+                .filter(|(y, z)| y.abs() + z.abs() <= padding)
+                .collect(),
+            Self::Square => square.collect(),
+            Self::Circle => {
+                let radius = padding as i16;
+                square
+                    .filter(|&(y, z)| {
+                        let (y, z) = (y as i16, z as i16);
+                        y * y + z * z <= radius * radius + radius
+                    })
+                    .collect()
+            }
+            Self::Cross => square.filter(|&(y, z)| y == 0 || z == 0).collect(),
+            Self::Custom(rows) => {
+                let half_rows = (rows.len() / 2) as i8;
+                rows.iter()
+                    .enumerate()
+                    .flat_map(|(y, row)| {
+                        let half_columns = (row.chars().count() / 2) as i8;
+                        row.chars()
+                            .enumerate()
+                            .filter(|(_, c)| *c == '#')
+                            .map(move |(z, _)| (y as i8 - half_rows, z as i8 - half_columns))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), MaskError> {
+        let Self::Custom(rows) = self else {
+            return Ok(());
+        };
+
+        let columns = rows.first().map_or(0, |row| row.chars().count());
+        if rows.iter().any(|row| row.chars().count() != columns) {
+            return Err(MaskError::UnevenRows);
+        }
+        if let Some(c) = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .find(|c| !"#.".contains(*c))
+        {
+            return Err(MaskError::InvalidCharacter(c));
+        }
+        if !rows.iter().any(|row| row.contains('#')) {
+            return Err(MaskError::Empty);
+        }
+        if rows.len() % 2 == 0 || columns % 2 == 0 {
+            return Err(MaskError::EvenDimensions {
+                rows: rows.len(),
+                columns,
+            });
+        }
+        if rows.len().max(columns) > Self::MAX_MASK_LENGTH {
+            return Err(MaskError::TooLarge {
+                rows: rows.len(),
+                columns,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether the size of the paddle changes the shape
+    pub const fn is_scalable(&self) -> bool {
+        !matches!(self, Self::Custom(_))
+    }
+}
+
+impl std::str::FromStr for PaddleShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diamond" => Ok(Self::Diamond),
+            "square" => Ok(Self::Square),
+            "circle" => Ok(Self::Circle),
+            "cross" => Ok(Self::Cross),
+            _ => Err(format!(
+                "{s} isn't a paddle shape, use diamond, square, circle or cross"
+            )),
+        }
+    }
+}
+
+/// The largest distance of a voxel from the center along the y and z axis
+pub fn extent(deltas: &[(i8, i8)]) -> (u8, u8) {
+    deltas.iter().fold((0, 0), |(y, z), (dy, dz)| {
+        (y.max(dy.unsigned_abs()), z.max(dz.unsigned_abs()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        let size = Odd::<u8>::new_panics(5);
+        assert_eq!(PaddleShape::Diamond.deltas(size).len(), 13);
+        assert_eq!(PaddleShape::Square.deltas(size).len(), 25);
+        assert_eq!(PaddleShape::Circle.deltas(size).len(), 21);
+        assert_eq!(PaddleShape::Cross.deltas(size).len(), 9);
+        assert_eq!(PaddleShape::Square.deltas(Odd::default()), vec![(0, 0)]);
+
+        let mask = PaddleShape::Custom(vec!["#.#".into(), ".#.".into(), "#.#".into()]);
+        assert_eq!(
+            mask.deltas(size),
+            vec![(-1, -1), (-1, 1), (0, 0), (1, -1), (1, 1)]
+        );
+        assert_eq!(extent(&mask.deltas(size)), (1, 1));
+        assert_eq!(extent(&PaddleShape::Cross.deltas(size)), (2, 2));
+    }
+
+    #[test]
+    fn test_masks() {
+        let mask = |rows: &[&str]| {
+            PaddleShape::Custom(rows.iter().map(|row| row.to_string()).collect()).validate()
+        };
+
+        assert_eq!(mask(&["###", ".#.", "..."]), Ok(()));
+        assert_eq!(mask(&["...", ".#"]), Err(MaskError::UnevenRows));
+        assert_eq!(mask(&["..."]), Err(MaskError::Empty));
+        assert_eq!(mask(&["#x#"]), Err(MaskError::InvalidCharacter('x')));
+        assert_eq!(
+            mask(&["##", "##"]),
+            Err(MaskError::EvenDimensions {
+                rows: 2,
+                columns: 2
+            })
+        );
+        let wide = format!("{}#{}", ".".repeat(64), ".".repeat(64));
+        assert_eq!(
+            mask(&[&wide]),
+            Err(MaskError::TooLarge {
+                rows: 1,
+                columns: 129
+            })
+        );
+    }
+}
@@ -1,92 +1,289 @@
-use std::sync::LazyLock;
-
 use crate::color::Rgb;
 use crate::odd::Odd;
-use crate::voxelbox::{self, Draw};
-
-pub const PAD_SIZE: Odd<u8> = Odd::<u8>::new_panics(5);
-
-pub static DRAWING_DELTAS: LazyLock<Vec<(i8, i8)>> = LazyLock::new(|| {
-    let size = PAD_SIZE.value() as i8;
-    let padding = (size - 1) / 2;
+use crate::voxelbox::Draw;
 
-    (-padding..=padding)
-        .flat_map(|y| {
-            let width = size - (y.abs() * 2);
-            let half = width / 2;
-            (-half..=half).map(move |x| (x, y))
-        })
-        .collect()
-});
+use super::{
+    collision::{Bounds, CollisionSide},
+    effect::TimedEffects,
+    paddle::{self, PaddleShape},
+    state,
+};
+use std::time::Instant;
 
-enum PlayerSite {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerSite {
     Left,
     Right,
+    Top,
+    Bottom,
 }
 
 impl PlayerSite {
-    const fn get_x(&self) -> (u8, u8) {
+    /// The two layers of the paddle along the axis towards its wall (x or y),
+    /// `depth` moves them towards the center of the arena
+    const fn get_layers(&self, bounds: &Bounds, depth: u8) -> (u8, u8) {
+        match self {
+            Self::Left | Self::Top => (depth, 1 + depth),
+            Self::Right => (bounds.width - 1 - depth, bounds.width - 2 - depth),
+            Self::Bottom => (bounds.height - 1 - depth, bounds.height - 2 - depth),
+        }
+    }
+
+    /// The lengths of the wall along the two axes the paddle moves on
+    pub const fn plane(&self, bounds: &Bounds) -> (u8, u8) {
+        match self {
+            Self::Left | Self::Right => (bounds.height, bounds.depth),
+            Self::Top | Self::Bottom => (bounds.width, bounds.depth),
+        }
+    }
+
+    /// The position of a voxel in the arena at `layer` and `(a, b)` on the plane of the wall
+    const fn voxel(&self, layer: u8, (a, b): (u8, u8)) -> (u8, u8, u8) {
         match self {
-            Self::Left => (0, 1),
-            Self::Right => (voxelbox::WIDTH - 1, voxelbox::WIDTH - 2),
+            Self::Left | Self::Right => (layer, a, b),
+            Self::Top | Self::Bottom => (a, layer, b),
         }
     }
+
+    /// The position `(a, b)` of a voxel or direction in the arena on the plane of the wall
+    pub fn project<T>(&self, (x, y, z): (T, T, T)) -> (T, T) {
+        match self {
+            Self::Left | Self::Right => (y, z),
+            Self::Top | Self::Bottom => (x, z),
+        }
+    }
+
+    /// How many voxels a voxel in the arena is away from the wall
+    pub const fn distance(&self, bounds: &Bounds, (x, y, _): (u8, u8, u8)) -> u8 {
+        let (wall, _) = self.get_layers(bounds, 0);
+        match self {
+            Self::Left | Self::Right => x.abs_diff(wall),
+            Self::Top | Self::Bottom => y.abs_diff(wall),
+        }
+    }
+
+    /// The voxel at `(a, b)` on the plane of the wall itself
+    pub const fn wall_voxel(&self, bounds: &Bounds, position: (u8, u8)) -> (u8, u8, u8) {
+        self.voxel(self.get_layers(bounds, 0).0, position)
+    }
+
+    /// The direction along the x (left, right) or y (top, bottom) axis in which the wall lies
+    pub const fn wall_direction(&self) -> i8 {
+        match self {
+            Self::Left | Self::Top => -1,
+            Self::Right | Self::Bottom => 1,
+        }
+    }
+
+    /// Whether a ball moving in `direction` doesn't move away from the wall.
+    /// Balls can move parallel to the top and bottom wall
+    pub const fn is_approached(&self, (x, y, _): (i8, i8, i8)) -> bool {
+        match self {
+            Self::Left | Self::Right => x != -self.wall_direction(),
+            Self::Top | Self::Bottom => y != -self.wall_direction(),
+        }
+    }
+
+    pub const fn from_side(side: &CollisionSide) -> Option<Self> {
+        match side {
+            CollisionSide::Left => Some(Self::Left),
+            CollisionSide::Right => Some(Self::Right),
+            CollisionSide::Top => Some(Self::Top),
+            CollisionSide::Bottom => Some(Self::Bottom),
+            CollisionSide::Front | CollisionSide::Back => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddleEffect {
+    Grow,
+    Shrink,
 }
 
 pub struct Player {
+    id: state::Player,
     color: Rgb,
     position: Position,
     site: PlayerSite,
+    bounds: Bounds,
+    shape: PaddleShape,
+    base_size: Odd<u8>,
+    deltas: Vec<(i8, i8)>,
+    effects: TimedEffects<PaddleEffect>,
+    max_depth: u8,
+    /// The distance of the paddle to its wall when it isn't pushed
+    offset: u8,
+    smashing: bool,
 }
 
+/// `y` and `x` are the first and second coordinate on the plane of the wall,
+/// for paddles on the left or right wall that is the y and z axis of the arena
 struct Position {
     x: u8,
     y: u8,
+    depth: u8,
 }
 
-impl Default for Position {
-    fn default() -> Self {
+impl Player {
+    /// Creates a player with the paddle centered at `(y, z)` on its wall
+    pub fn new(
+        id: state::Player,
+        site: PlayerSite,
+        color: Rgb,
+        bounds: Bounds,
+        (shape, size): (PaddleShape, Odd<u8>),
+        (y, z): (u8, u8),
+    ) -> Self {
         Self {
-            x: voxelbox::DEEPTH / 2,
-            y: voxelbox::HEIGHT / 2,
+            id,
+            color,
+            position: Position { x: z, y, depth: 0 },
+            site,
+            bounds,
+            deltas: shape.deltas(size),
+            shape,
+            base_size: size,
+            effects: TimedEffects::default(),
+            max_depth: 0,
+            offset: 0,
+            smashing: false,
         }
     }
-}
 
-impl Player {
-    pub fn player_1() -> Self {
-        Self {
-            color: Rgb::green(),
-            position: Position::default(),
-            site: PlayerSite::Left,
+    /// Allows the paddle to be pushed up to `max_depth` voxels into the arena
+    pub const fn with_max_depth(mut self, max_depth: u8) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Moves the paddle `offset` voxels away from its wall, e.g. for a forward in front of a goalie
+    pub const fn with_offset(mut self, offset: u8) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn add_effect(&mut self, effect: PaddleEffect, until: Instant) {
+        self.effects.add(effect, until);
+        self.resize();
+    }
+
+    pub fn update_effects(&mut self, now: Instant) {
+        if self.effects.update(now) {
+            self.resize();
         }
     }
-    pub fn player_2() -> Self {
-        Self {
-            color: Rgb::yellow(),
-            position: Position::default(),
-            site: PlayerSite::Right,
+
+    /// Every growing effect makes the paddle 2 voxels bigger, every shrinking one 2 voxels smaller.
+    /// Custom shapes keep their size
+    fn resize(&mut self) {
+        if !self.shape.is_scalable() {
+            return;
         }
+
+        let grow = self.effects.count(&PaddleEffect::Grow) as i16;
+        let shrink = self.effects.count(&PaddleEffect::Shrink) as i16;
+        let (length_a, length_b) = self.site.plane(&self.bounds);
+        let max_size = length_a.min(length_b) as i16;
+        let max_size = if max_size % 2 == 0 {
+            max_size - 1
+        } else {
+            max_size
+        };
+
+        let size = (self.base_size.value() as i16 + 2 * (grow - shrink)).clamp(1, max_size);
+        self.deltas = self.shape.deltas(Odd::<u8>::new_panics(size as u8));
+
+        // Keep the resized paddle inside the arena
+        self.inc_x(0);
+        self.inc_y(0);
     }
 
+    /// The layers towards the wall and the center `(y, z)` on the plane of the wall
     pub const fn full_position(&self) -> ((u8, u8), u8, u8) {
-        (self.site.get_x(), self.position.y, self.position.x)
+        (
+            self.site
+                .get_layers(&self.bounds, self.offset + self.position.depth),
+            self.position.y,
+            self.position.x,
+        )
     }
 
-    pub fn inc_x(&mut self, x: i16) {
-        let padding = (PAD_SIZE - 1) / 2;
-        let lower_limit: i16 = padding.into();
-        let upper_limit: i16 = (voxelbox::DEEPTH - 1 - padding).into();
+    /// Every voxel of the paddle in the arena
+    pub fn voxels(&self) -> Vec<(u8, u8, u8)> {
+        let ((layer_1, layer_2), y, z) = self.full_position();
+        self.deltas
+            .iter()
+            .flat_map(|(delta_y, delta_z)| {
+                let a = (*delta_y + y as i8) as u8;
+                let b = (*delta_z + z as i8) as u8;
+                [
+                    self.site.voxel(layer_1, (a, b)),
+                    self.site.voxel(layer_2, (a, b)),
+                ]
+            })
+            .collect()
+    }
 
-        self.position.x = (self.position.x as i16 + x).clamp(lower_limit, upper_limit) as u8;
+    pub const fn id(&self) -> state::Player {
+        self.id
     }
 
-    pub fn inc_y(&mut self, y: i16) {
-        let padding = (PAD_SIZE - 1) / 2;
-        let lower_limit: i16 = padding.into();
-        let upper_limit: i16 = (voxelbox::HEIGHT - 1 - padding).into();
+    #[cfg(test)]
+    pub fn deltas(&self) -> &[(i8, i8)] {
+        &self.deltas
+    }
 
-        self.position.y = (self.position.y as i16 + y).clamp(lower_limit, upper_limit) as u8;
+    pub const fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub const fn site(&self) -> &PlayerSite {
+        &self.site
+    }
+
+    pub const fn depth(&self) -> u8 {
+        self.position.depth
+    }
+
+    pub const fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+
+    /// Whether the paddle is currently pushed forward, a hit while smashing speeds the ball up
+    pub const fn is_smashing(&self) -> bool {
+        self.smashing
+    }
+
+    pub fn set_smashing(&mut self, smashing: bool) {
+        self.smashing = smashing;
+    }
+
+    pub fn inc_depth(&mut self, depth: i16) {
+        self.position.depth =
+            (self.position.depth as i16 + depth).clamp(0, self.max_depth.into()) as u8;
+    }
+
+    /// The lowest and highest center of the paddle along both axes of the wall
+    pub fn limits(&self) -> ((u8, u8), (u8, u8)) {
+        let (padding_y, padding_z) = paddle::extent(&self.deltas);
+        let (length_y, length_z) = self.site.plane(&self.bounds);
+        (
+            (padding_y, length_y - 1 - padding_y),
+            (padding_z, length_z - 1 - padding_z),
+        )
+    }
+
+    pub fn inc_x(&mut self, x: i16) {
+        let (_, (lower_limit, upper_limit)) = self.limits();
+        self.position.x =
+            (self.position.x as i16 + x).clamp(lower_limit.into(), upper_limit.into()) as u8;
+    }
+
+    pub fn inc_y(&mut self, y: i16) {
+        let ((lower_limit, upper_limit), _) = self.limits();
+        self.position.y =
+            (self.position.y as i16 + y).clamp(lower_limit.into(), upper_limit.into()) as u8;
     }
 }
 
@@ -96,19 +293,42 @@ impl Draw for Player {
     }
 
     fn draw(&self) -> Vec<(usize, usize, usize)> {
-        let (x1, x2) = self.site.get_x();
-        let x1 = x1 as usize;
-        let x2 = x2 as usize;
-
-        let y = self.position.y;
-        let z = self.position.x;
-        (*DRAWING_DELTAS)
-            .iter()
-            .flat_map(|(delta_y, delta_z)| {
-                let y = (delta_y + y as i8) as usize;
-                let z = (delta_z + z as i8) as usize;
-                [(x1, y, z), (x2, y, z)]
-            })
+        self.voxels()
+            .into_iter()
+            .map(|(x, y, z)| (x.into(), y.into(), z.into()))
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::Level;
+    use std::time::Duration;
+
+    #[test]
+    fn test_paddle_effects() {
+        let now = Instant::now();
+        let mut player = Level::default().player(state::Player::Player1);
+        assert_eq!(player.deltas().len(), 13);
+
+        player.add_effect(PaddleEffect::Grow, now + Duration::from_secs(2));
+        assert_eq!(player.deltas().len(), 25);
+
+        player.add_effect(PaddleEffect::Shrink, now + Duration::from_secs(1));
+        player.add_effect(PaddleEffect::Shrink, now + Duration::from_secs(1));
+        player.add_effect(PaddleEffect::Shrink, now + Duration::from_secs(1));
+        assert_eq!(player.deltas().len(), 1);
+
+        player.update_effects(now + Duration::from_secs(1));
+        assert_eq!(player.deltas().len(), 25);
+
+        // A paddle at the edge stays inside of the arena when it grows
+        player.inc_y(-20);
+        player.add_effect(PaddleEffect::Grow, now + Duration::from_secs(2));
+        assert_eq!(player.full_position().1, 4);
+
+        player.update_effects(now + Duration::from_secs(2));
+        assert_eq!(player.deltas().len(), 13);
+    }
+}
@@ -0,0 +1,213 @@
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+use crate::{color::Rgb, log, random, voxelbox::Draw};
+
+use super::{
+    ball::{Ball, BallEffect},
+    level::Level,
+    multiball::Multiball,
+    obstacle::Obstacle,
+    player::{PaddleEffect, Player},
+};
+
+const MAX_POWERUPS: usize = 3;
+const SPAWN_ATTEMPTS: u8 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    BiggerPaddle,
+    ShrinkOpponent,
+    SlowMotion,
+    InvisibleBall,
+    ExtraBall,
+}
+
+impl PowerUpKind {
+    const ALL: [Self; 5] = [
+        Self::BiggerPaddle,
+        Self::ShrinkOpponent,
+        Self::SlowMotion,
+        Self::InvisibleBall,
+        Self::ExtraBall,
+    ];
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerUpSettings {
+    /// Spawn a power-up after this duration passed, `None` disables power-ups
+    pub interval: Option<Duration>,
+    /// How long the effects of a power-up last
+    pub duration: Duration,
+}
+
+pub struct PowerUp {
+    kind: PowerUpKind,
+    position: (u8, u8, u8),
+}
+
+impl Draw for PowerUp {
+    fn color(&self) -> Rgb {
+        match self.kind {
+            PowerUpKind::BiggerPaddle => Rgb::lime(),
+            PowerUpKind::ShrinkOpponent => Rgb::red(),
+            PowerUpKind::SlowMotion => Rgb::blue(),
+            PowerUpKind::InvisibleBall => Rgb::white(),
+            PowerUpKind::ExtraBall => Rgb::orange(),
+        }
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        let (x, y, z) = self.position;
+        vec![(x.into(), y.into(), z.into())]
+    }
+}
+
+pub struct PowerUps {
+    settings: PowerUpSettings,
+    items: Vec<PowerUp>,
+    last_spawn: Instant,
+}
+
+impl PowerUps {
+    pub fn new(settings: PowerUpSettings) -> Self {
+        Self {
+            settings,
+            items: Vec::new(),
+            last_spawn: Instant::now(),
+        }
+    }
+
+    pub fn items(&self) -> &[PowerUp] {
+        &self.items
+    }
+
+    /// Removes every power-up that wasn't collected
+    pub fn reset(&mut self, now: Instant) {
+        self.items.clear();
+        self.last_spawn = now;
+    }
+
+    /// Spawns new power-ups and activates the ones a ball passes through,
+    /// extra balls are only added until the maximum of `multiball` is reached
+    pub fn update(
+        &mut self,
+        now: Instant,
+        balls: &mut Vec<(Ball, Instant)>,
+        players: &mut [Player],
+        multiball: &Multiball,
+        level: &Level,
+        obstacles: &[Obstacle],
+    ) {
+        let Some(interval) = self.settings.interval else {
+            return;
+        };
+
+        if now.duration_since(self.last_spawn) >= interval {
+            self.last_spawn = now;
+            if self.items.len() < MAX_POWERUPS {
+                self.spawn(level, obstacles);
+            }
+        }
+
+        let until = now + self.settings.duration;
+        for i in 0..balls.len() {
+            let Some(credited) = balls[i].0.last_hit() else {
+                continue;
+            };
+            let Some(index) = self
+                .items
+                .iter()
+                .position(|item| balls[i].0.covers(item.position))
+            else {
+                continue;
+            };
+
+            let kind = self.items.swap_remove(index).kind;
+            log!(Log, "{} collected {:?}", credited, kind);
+
+            match kind {
+                PowerUpKind::BiggerPaddle => players
+                    .iter_mut()
+                    .filter(|player| player.id() == credited)
+                    .for_each(|player| player.add_effect(PaddleEffect::Grow, until)),
+                PowerUpKind::ShrinkOpponent => players
+                    .iter_mut()
+                    .filter(|player| player.id() != credited)
+                    .for_each(|player| player.add_effect(PaddleEffect::Shrink, until)),
+                PowerUpKind::SlowMotion => balls[i].0.add_effect(BallEffect::SlowMotion, until),
+                PowerUpKind::InvisibleBall => balls[i].0.add_effect(BallEffect::Invisible, until),
+                PowerUpKind::ExtraBall => {
+                    multiball.add_ball(balls, level, now);
+                }
+            }
+        }
+    }
+
+    /// Places a random power-up on a free voxel between the paddles
+    fn spawn(&mut self, level: &Level, obstacles: &[Obstacle]) {
+        let bounds = level.bounds();
+        let blocked: Vec<_> = obstacles.iter().flat_map(Obstacle::voxels).collect();
+
+        random::with_rng(|rng| {
+            for _ in 0..SPAWN_ATTEMPTS {
+                let position = (
+                    rng.random_range(3..bounds.width - 3),
+                    rng.random_range(1..bounds.height - 1),
+                    rng.random_range(1..bounds.depth - 1),
+                );
+                if blocked.contains(&position) {
+                    continue;
+                }
+
+                let kind = PowerUpKind::ALL[rng.random_range(0..PowerUpKind::ALL.len())];
+                self.items.push(PowerUp { kind, position });
+                return;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{multiball::MultiballSettings, state};
+    use std::num::NonZero;
+
+    #[test]
+    fn test_extra_ball() {
+        let level = Level::default();
+        let mut powerups = PowerUps::new(PowerUpSettings {
+            interval: Some(Duration::from_secs(3600)),
+            duration: Duration::from_secs(1),
+        });
+        let multiball = |max_balls| {
+            Multiball::new(MultiballSettings {
+                paddle_hits: None,
+                interval: None,
+                max_balls: NonZero::new(max_balls).unwrap(),
+            })
+        };
+        let mut ball = level.ball();
+        ball.hit_by(state::Player::Player1);
+        let (x, y, z) = ball.draw()[0];
+        let position = (x as u8, y as u8, z as u8);
+        let mut balls = vec![(ball, Instant::now())];
+        let mut players = level.players();
+
+        let mut collect = |balls: &mut Vec<_>, multiball: &Multiball| {
+            powerups.items.push(PowerUp {
+                kind: PowerUpKind::ExtraBall,
+                position,
+            });
+            powerups.update(Instant::now(), balls, &mut players, multiball, &level, &[]);
+            assert!(powerups.items.is_empty());
+        };
+
+        // The extra ball doesn't exceed the maximum number of balls
+        collect(&mut balls, &multiball(1));
+        assert_eq!(balls.len(), 1);
+        collect(&mut balls, &multiball(2));
+        assert_eq!(balls.len(), 2);
+    }
+}
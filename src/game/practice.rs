@@ -0,0 +1,128 @@
+use crate::{color::Rgb, log, voxelbox::Draw};
+
+use super::{ball::Ball, collision::Bounds, level::Level, player::Player};
+
+/// A single player practicing against the opposite wall, every return counts until the
+/// ball is missed
+#[derive(Debug, Default)]
+pub struct Practice {
+    returns: u16,
+    high_score: u16,
+}
+
+impl Practice {
+    pub fn returns(&self) -> u16 {
+        self.returns
+    }
+
+    pub fn high_score(&self) -> u16 {
+        self.high_score
+    }
+
+    pub fn add_returns(&mut self, returns: u8) {
+        self.returns = self.returns.saturating_add(returns.into());
+    }
+
+    /// Ends the current run, returns whether it set a new high score
+    pub fn miss(&mut self) -> bool {
+        let new_high_score = self.returns > self.high_score;
+        self.high_score = self.high_score.max(self.returns);
+        self.returns = 0;
+        new_high_score
+    }
+}
+
+/// Ends the run after a miss and serves a new ball to the player
+pub fn end_run_and_reset(practice: &mut Practice, level: &Level) -> (Vec<Player>, Ball) {
+    let returns = practice.returns();
+    if practice.miss() {
+        log!(Success, "New high score: {} returns", returns);
+    } else {
+        log!(
+            Log,
+            "Missed after {} returns (high score {})",
+            returns,
+            practice.high_score()
+        );
+    }
+
+    let players = level.players();
+    let ball = match players.first() {
+        Some(player) => level.ball().served_to(player.site()),
+        None => level.ball(),
+    };
+    (players, ball)
+}
+
+/// A score shown as voxels filling a horizontal plane of the cube from the back,
+/// the current run on the ceiling and the high score on the floor
+pub struct ScoreBar<'a> {
+    bounds: &'a Bounds,
+    score: u16,
+    y: u8,
+    color: Rgb,
+}
+
+impl<'a> ScoreBar<'a> {
+    pub fn run(bounds: &'a Bounds, practice: &Practice, player: &Player) -> Self {
+        Self {
+            bounds,
+            score: practice.returns,
+            y: 0,
+            color: player.color(),
+        }
+    }
+
+    pub const fn high_score(bounds: &'a Bounds, practice: &Practice) -> Self {
+        Self {
+            bounds,
+            score: practice.high_score,
+            y: bounds.height - 1,
+            color: Rgb::gray(),
+        }
+    }
+}
+
+impl Draw for ScoreBar<'_> {
+    fn color(&self) -> Rgb {
+        self.color
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        let width = self.bounds.width as usize;
+        let depth = self.bounds.depth as usize;
+
+        (0..(self.score as usize).min(width * depth))
+            .map(|index| (index % width, self.y.into(), depth - 1 - index / width))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::state;
+
+    #[test]
+    fn test_practice() {
+        let mut practice = Practice::default();
+        practice.add_returns(3);
+        assert!(practice.miss());
+        practice.add_returns(1);
+        assert!(!practice.miss());
+        practice.add_returns(22);
+        assert_eq!(practice.returns(), 22);
+
+        let level = Level::default().with_players(Some(1)).unwrap();
+        let bounds = Bounds::default();
+        let player = level.player(state::Player::Player1);
+        let run = ScoreBar::run(&bounds, &practice, &player).draw();
+        assert_eq!(run.len(), 22);
+        assert_eq!(run[0], (0, 0, 11));
+        assert_eq!(run[21], (1, 0, 10));
+        assert_eq!(
+            ScoreBar::high_score(&bounds, &practice).draw(),
+            [(0, 19, 11), (1, 19, 11), (2, 19, 11)]
+        );
+    }
+}
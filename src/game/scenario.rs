@@ -0,0 +1,114 @@
+use std::num::NonZero;
+
+use super::{
+    ball::Ball,
+    ball_movement::{move_ball_and_score, BallEvent},
+    collision::{Collision, CollisionSide},
+    level::{Level, TeamLayout},
+    obstacle::{Obstacle, ObstacleDefinition},
+    player::Player,
+    state,
+};
+
+/// Places a ball and the paddles at fixed coordinates to test collisions without a game loop
+pub struct Scenario {
+    pub ball: Ball,
+    pub players: Vec<Player>,
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self {
+            ball: Ball::default(),
+            players: Level::default().players(),
+            obstacles: Vec::new(),
+        }
+    }
+
+    /// Adds players 3 and 4 on the top and bottom wall
+    pub fn four_players() -> Self {
+        Self {
+            players: Level::default()
+                .with_players(Some(4))
+                .expect("The default arena fits four players")
+                .players(),
+            ..Self::new()
+        }
+    }
+
+    pub fn ball(mut self, position: (u8, u8, u8), direction: (i8, i8, i8)) -> Self {
+        let (x, y, z) = direction;
+        let x = NonZero::new(x).expect("The ball always moves along the x axis");
+        self.ball = Ball::default().with_direction((x, y, z));
+        self.ball.position = position;
+        self
+    }
+
+    /// Two teams with players 3 and 4 next to or behind players 1 and 2
+    pub fn teams(layout: TeamLayout) -> Self {
+        Self {
+            players: Level::default()
+                .with_teams(Some(layout))
+                .expect("The default arena fits two teams")
+                .players(),
+            ..Self::new()
+        }
+    }
+
+    pub fn player_1(self, y: u8, z: u8) -> Self {
+        self.player(state::Player::Player1, y, z)
+    }
+
+    pub fn player_2(self, y: u8, z: u8) -> Self {
+        self.player(state::Player::Player2, y, z)
+    }
+
+    /// Moves the paddle of `player` to `(y, z)` on its wall
+    pub fn player(mut self, player: state::Player, y: u8, z: u8) -> Self {
+        move_player_to(&mut self.players[player.index()], y, z);
+        self
+    }
+
+    /// Pushes the paddle of player 1 `depth` voxels into the arena
+    pub fn push_player_1(mut self, depth: u8) -> Self {
+        self.players[0] = Level::default()
+            .with_paddle_depth(Some(depth))
+            .expect("The depth fits into the default arena")
+            .player(state::Player::Player1);
+        self.players[0].inc_depth(depth.into());
+        self
+    }
+
+    pub fn obstacle(mut self, definition: ObstacleDefinition) -> Self {
+        self.obstacles
+            .push(Obstacle::new(definition, *self.ball.bounds()));
+        self
+    }
+
+    pub fn bounds(&self) -> Vec<CollisionSide> {
+        self.ball.collides(self.ball.bounds())
+    }
+
+    pub fn paddles(&self) -> (bool, bool) {
+        (
+            self.ball.collides(&self.players[0]),
+            self.ball.collides(&self.players[1]),
+        )
+    }
+
+    pub fn step(&mut self) -> Option<BallEvent> {
+        move_ball_and_score(
+            &mut self.ball,
+            &self.players,
+            std::iter::empty(),
+            &self.obstacles,
+        )
+    }
+}
+
+fn move_player_to(player: &mut Player, y: u8, z: u8) {
+    let (_, current_y, current_z) = player.full_position();
+    player.inc_y(y as i16 - current_y as i16);
+    player.inc_x(z as i16 - current_z as i16);
+}
@@ -0,0 +1,270 @@
+use crate::{
+    framework::Status,
+    input::{Bot, Difficulty, PlayerInput},
+    log, random,
+};
+use std::{fmt::Write, num::NonZero, time::Duration};
+
+use super::{
+    state::{GameState, Scorer},
+    GameSettings, Pong,
+};
+
+/// The simulated time between two updates
+const TICK: Duration = Duration::from_millis(10);
+/// Matches still running after this simulated time end without a winner
+const MAX_MATCH_TIME: Duration = Duration::from_secs(60 * 60);
+/// A rally with this many paddle hits ends the match as a draw, e.g. two hard bots never miss
+const MAX_RALLY: u32 = 300;
+
+pub struct SimulationSettings {
+    pub matches: NonZero<u32>,
+    /// The seed of the first match, every following match uses the next seed
+    pub seed: u64,
+    /// The difficulty of the bot controlling player 1 to 4, only the players of the level are used
+    pub bots: [Difficulty; 4],
+    /// The match that is played, every player is replaced by a bot
+    pub game: GameSettings,
+}
+
+/// What happened in a single simulated match
+#[derive(Debug, PartialEq)]
+pub struct MatchStats {
+    /// `None` for a draw, the match reached the time limit or a rally reached the cap
+    pub winner: Option<Scorer>,
+    /// The goals conceded by every player
+    pub conceded: [u32; 4],
+    /// The paddle hits of every rally that ended with a goal
+    pub rallies: Vec<u32>,
+    /// The shortest time between two steps of a ball
+    pub fastest: Duration,
+    pub duration: Duration,
+}
+
+/// Plays a match between bots without a gamepad, network or real-time clock.
+/// The match is the same [`Pong`] as on the cube, but the time only advances by a fixed tick
+/// between two updates and the same `seed` replays the same match
+pub fn simulate_match(settings: &SimulationSettings, seed: u64) -> MatchStats {
+    random::seed(seed);
+    let mut game = settings.game.clone();
+    for (player, difficulty) in game.players.iter_mut().zip(settings.bots) {
+        player.bot = Some(difficulty);
+    }
+    let players = game.level.active_players().len();
+    let mut inputs: Vec<_> = settings.bots[..players]
+        .iter()
+        .map(|&difficulty| PlayerInput::Bot(Bot::new(difficulty)))
+        .collect();
+    let mut pong = Pong::new(game);
+    let start = pong.now;
+
+    let mut stats = MatchStats {
+        winner: None,
+        conceded: [0; 4],
+        rallies: Vec::new(),
+        fastest: pong.balls[0].0.intervall(),
+        duration: Duration::ZERO,
+    };
+    let mut rally = 0;
+
+    log::muted(|| {
+        while pong.now.duration_since(start) < MAX_MATCH_TIME {
+            let step = pong.step(TICK, &mut inputs);
+            rally += u32::from(step.paddle_hits);
+            for (ball, _) in &pong.balls {
+                stats.fastest = stats.fastest.min(ball.intervall());
+            }
+            if let Some(goal) = step.goal {
+                stats.conceded[goal.conceded.index()] += 1;
+                stats.rallies.push(rally);
+                rally = 0;
+            }
+            if step.status == Status::Finished {
+                stats.winner = pong.state.winner(pong.now);
+                break;
+            }
+            if rally >= MAX_RALLY {
+                break;
+            }
+        }
+    });
+
+    stats.duration = pong.now.duration_since(start);
+    stats
+}
+
+/// Plays every match and writes a line per match as CSV
+pub fn csv(settings: &SimulationSettings) -> String {
+    let mut csv = String::from(
+        "match,seed,winner,conceded_p1,conceded_p2,conceded_p3,conceded_p4,rallies,average_rally,\
+         longest_rally,fastest_ms,duration_s\n",
+    );
+    for index in 1..=settings.matches.get() {
+        let seed = settings.seed.wrapping_add((index - 1).into());
+        let stats = simulate_match(settings, seed);
+        let winner = stats
+            .winner
+            .map(|winner| winner.to_string())
+            .unwrap_or_default();
+        let [p1, p2, p3, p4] = stats.conceded;
+        let _ = writeln!(
+            csv,
+            "{index},{seed},{winner},{p1},{p2},{p3},{p4},{},{:.2},{},{},{:.1}",
+            stats.rallies.len(),
+            average(&stats.rallies),
+            stats.rallies.iter().max().unwrap_or(&0),
+            stats.fastest.as_millis(),
+            stats.duration.as_secs_f32(),
+        );
+    }
+    csv
+}
+
+/// Plays every match and sums them up in a table
+pub fn summary(settings: &SimulationSettings) -> String {
+    let all: Vec<_> = (0..settings.matches.get())
+        .map(|index| simulate_match(settings, settings.seed.wrapping_add(index.into())))
+        .collect();
+    let level = &settings.game.level;
+    let players = level.active_players();
+    let state = GameState::new(
+        players.len(),
+        level.teams().is_some(),
+        settings.game.win_condition,
+    );
+    let rallies: Vec<_> = all.iter().flat_map(|stats| stats.rallies.clone()).collect();
+    let goals = rallies.len().max(1) as f32;
+
+    let mut table = String::new();
+    let mut row = |name: &str, value: String| {
+        let _ = writeln!(table, "{name:<24}{value:>12}");
+    };
+    row("Matches", all.len().to_string());
+    row("Seed", settings.seed.to_string());
+    for scorer in state.scorers() {
+        let wins = all
+            .iter()
+            .filter(|stats| stats.winner == Some(scorer))
+            .count();
+        row(&format!("Wins {scorer}"), wins.to_string());
+    }
+    for player in players {
+        let conceded: u32 = all.iter().map(|stats| stats.conceded[player.index()]).sum();
+        row(
+            &format!("Conceded {player}"),
+            format!("{:.1}%", conceded as f32 / goals * 100.0),
+        );
+    }
+    let draws = all.iter().filter(|stats| stats.winner.is_none()).count();
+    row("Draws", draws.to_string());
+    row("Average rally", format!("{:.2}", average(&rallies)));
+    row(
+        "Longest rally",
+        rallies.iter().max().unwrap_or(&0).to_string(),
+    );
+    let fastest = all
+        .iter()
+        .map(|stats| stats.fastest)
+        .min()
+        .unwrap_or_default();
+    let average_fastest = all
+        .iter()
+        .map(|stats| stats.fastest.as_millis() as f32)
+        .sum::<f32>()
+        / all.len() as f32;
+    row("Fastest step", format!("{}ms", fastest.as_millis()));
+    row("Average fastest step", format!("{average_fastest:.0}ms"));
+    let duration = all
+        .iter()
+        .map(|stats| stats.duration.as_secs_f32())
+        .sum::<f32>()
+        / all.len() as f32;
+    row("Average duration", format!("{duration:.1}s"));
+    table
+}
+
+fn average(values: &[u32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<u32>() as f32 / values.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{
+            input::ControlMode,
+            level::Level,
+            multiball::MultiballSettings,
+            powerup::PowerUpSettings,
+            state::{SetRules, WinCondition},
+            PlayerSettings,
+        },
+        positive::Positive,
+    };
+
+    fn settings(bots: [Difficulty; 4]) -> SimulationSettings {
+        SimulationSettings {
+            matches: NonZero::new(3).unwrap(),
+            seed: 0x5eed,
+            bots,
+            game: GameSettings {
+                players: [(); 4].map(|_| PlayerSettings {
+                    sensitivity: Positive::new(1.0).unwrap(),
+                    control: ControlMode::Absolute,
+                    bot: None,
+                }),
+                connectivity: ("127.0.0.1".to_string(), 0),
+                win_condition: WinCondition::Points(NonZero::new(2).unwrap()),
+                set_rules: SetRules::default(),
+                multiball: MultiballSettings {
+                    paddle_hits: NonZero::new(4),
+                    interval: None,
+                    max_balls: NonZero::new(2).unwrap(),
+                },
+                powerups: PowerUpSettings {
+                    interval: Some(Duration::from_secs(5)),
+                    duration: Duration::from_secs(3),
+                },
+                level: Level::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_simulation() {
+        let settings = settings([
+            Difficulty::Hard,
+            Difficulty::Easy,
+            Difficulty::Medium,
+            Difficulty::Medium,
+        ]);
+
+        let stats = simulate_match(&settings, settings.seed);
+        assert_eq!(stats, simulate_match(&settings, settings.seed));
+        let winner = stats.winner.expect("A short match has a winner");
+        let Scorer::Player(winner) = winner else {
+            panic!("Without teams players win");
+        };
+        assert_eq!(stats.rallies.len() as u32, stats.conceded.iter().sum());
+        assert!(stats.conceded[winner.index()] < 2);
+        assert!(stats.fastest <= Duration::from_millis(600));
+
+        let csv = csv(&settings);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(summary(&settings).starts_with("Matches"));
+    }
+
+    #[test]
+    fn test_hard_bots() {
+        let mut settings = settings([Difficulty::Hard; 4]);
+        settings.game.multiball.paddle_hits = None;
+        settings.game.powerups.interval = None;
+
+        let stats = simulate_match(&settings, settings.seed);
+        assert_eq!(stats.winner, None, "Hard bots never miss");
+        assert!(stats.duration < MAX_MATCH_TIME / 2);
+    }
+}
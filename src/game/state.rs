@@ -1,44 +1,448 @@
-use std::{fmt, num::NonZero};
+use std::{
+    fmt,
+    num::NonZero,
+    time::{Duration, Instant},
+};
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct GameState {
-    player_1_points: u8,
-    player_2_points: u8,
+    points: [u8; 4],
+    lives: [u8; 4],
+    players: usize,
+    teams: bool,
+    condition: WinCondition,
+    rules: SetRules,
+    sets: [u8; 4],
+    started: Instant,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinCondition {
+    /// The first to reach the points wins
+    Points(NonZero<u8>),
+    /// Everyone starts with the lives and loses one when the ball reaches their wall,
+    /// the last one standing wins
+    Lives(NonZero<u8>),
+    /// The one with the most points after the duration wins,
+    /// on a tie the game continues until someone leads
+    Time(Duration),
+}
+
+/// How a set is won when playing for points and how many sets are played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetRules {
+    /// A set is only won with a lead of two points, like deuce in table tennis
+    pub win_by_two: bool,
+    /// Whoever reaches this many points wins the set even without a lead of two
+    pub cap: Option<NonZero<u8>>,
+    /// The match is won by whoever wins the majority of this many sets, always odd
+    pub best_of: NonZero<u8>,
+}
+
+impl Default for SetRules {
+    fn default() -> Self {
+        Self {
+            win_by_two: false,
+            cap: None,
+            best_of: NonZero::<u8>::MIN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Player {
     Player1,
     Player2,
+    Player3,
+    Player4,
+}
+
+/// Who collects the points, a single player or a team of players 1 and 3 or players 2 and 4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scorer {
+    Player(Player),
+    Team(u8),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GoalResult {
+    Scored(Vec<Scorer>),
+    /// The point decided the set, the points start over for the next one
+    WonSet(Scorer),
+    /// The point decided the last set and with it the match, the points stay
+    WonMatch(Scorer),
+    LostLife(Scorer),
+    Eliminated(Scorer),
+}
+
+impl Player {
+    pub const ALL: [Self; 4] = [Self::Player1, Self::Player2, Self::Player3, Self::Player4];
+
+    pub const fn index(&self) -> usize {
+        match self {
+            Self::Player1 => 0,
+            Self::Player2 => 1,
+            Self::Player3 => 2,
+            Self::Player4 => 3,
+        }
+    }
 }
 
 impl fmt::Display for Player {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Player {}", self.index() + 1)
+    }
+}
+
+impl Scorer {
+    const fn index(&self) -> usize {
         match self {
-            Self::Player1 => f.write_str("Player 1"),
-            Self::Player2 => f.write_str("Player 2"),
+            Self::Player(player) => player.index(),
+            Self::Team(team) => *team as usize - 1,
+        }
+    }
+}
+
+impl fmt::Display for Scorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Player(player) => player.fmt(f),
+            Self::Team(team) => write!(f, "Team {team}"),
         }
     }
 }
 
 impl GameState {
-    pub fn score(&mut self, player: &Player) {
-        match player {
-            Player::Player1 => self.player_1_points += 1,
-            Player::Player2 => self.player_2_points += 1,
+    /// Creates the state for the first `players` players, with `teams` players 1 and 3
+    /// play against players 2 and 4
+    pub fn new(players: usize, teams: bool, condition: WinCondition) -> Self {
+        let lives = match condition {
+            WinCondition::Lives(lives) => lives.get(),
+            WinCondition::Points(_) | WinCondition::Time(_) => 0,
+        };
+        Self {
+            points: [0; 4],
+            lives: [lives; 4],
+            players,
+            teams,
+            condition,
+            rules: SetRules::default(),
+            sets: [0; 4],
+            started: Instant::now(),
         }
     }
 
-    pub fn winner(&self, winning_points: NonZero<u8>) -> Option<Player> {
-        if self.player_1_points >= winning_points.into() {
-            Some(Player::Player1)
-        } else if self.player_2_points >= winning_points.into() {
-            Some(Player::Player2)
+    /// Plays for points with the `rules` for sets, only used with `WinCondition::Points`
+    pub const fn with_rules(mut self, rules: SetRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &Player::ALL[..self.players]
+    }
+
+    pub const fn scorer(&self, player: &Player) -> Scorer {
+        if self.teams {
+            Scorer::Team(player.index() as u8 % 2 + 1)
         } else {
-            None
+            Scorer::Player(*player)
+        }
+    }
+
+    /// Every player or team collecting points, in order
+    pub fn scorers(&self) -> Vec<Scorer> {
+        let mut scorers: Vec<_> = self
+            .players()
+            .iter()
+            .map(|player| self.scorer(player))
+            .collect();
+        scorers.sort_by_key(Scorer::index);
+        scorers.dedup();
+        scorers
+    }
+
+    /// Scores a ball reaching the wall of `conceded`, the player who hit it last gets the point.
+    /// Without such a player, e.g. after an own goal, everyone else gets a point.
+    /// When playing with lives `conceded` loses a life instead
+    pub fn score_goal(&mut self, conceded: &Player, hit_by: Option<Player>) -> GoalResult {
+        let conceded = self.scorer(conceded);
+        if let WinCondition::Lives(_) = self.condition {
+            let lives = &mut self.lives[conceded.index()];
+            *lives = lives.saturating_sub(1);
+            return if *lives == 0 {
+                GoalResult::Eliminated(conceded)
+            } else {
+                GoalResult::LostLife(conceded)
+            };
+        }
+
+        let scorers = match hit_by.map(|player| self.scorer(&player)) {
+            Some(scorer) if scorer != conceded => vec![scorer],
+            _ => self
+                .scorers()
+                .into_iter()
+                .filter(|&scorer| scorer != conceded)
+                .collect(),
+        };
+        for scorer in &scorers {
+            self.points[scorer.index()] += 1;
+        }
+
+        if let Some(set_winner) = self.set_winner() {
+            self.sets[set_winner.index()] += 1;
+            // The points of the last set stay to show the final score
+            if self.winner(Instant::now()).is_some() {
+                return GoalResult::WonMatch(set_winner);
+            }
+            self.points = [0; 4];
+            return GoalResult::WonSet(set_winner);
+        }
+        GoalResult::Scored(scorers)
+    }
+
+    /// The player or team winning the current set when playing for points
+    fn set_winner(&self) -> Option<Scorer> {
+        let WinCondition::Points(winning_points) = self.condition else {
+            return None;
+        };
+        let leader = self.leader()?;
+        let points = self.points[leader.index()];
+        let runner_up = self
+            .scorers()
+            .into_iter()
+            .filter(|&scorer| scorer != leader)
+            .map(|scorer| self.points[scorer.index()])
+            .max()
+            .unwrap_or(0);
+
+        let capped = self.rules.cap.is_some_and(|cap| points >= cap.get());
+        let lead = if self.rules.win_by_two { 2 } else { 1 };
+        (points >= winning_points.get() && (points - runner_up >= lead || capped)).then_some(leader)
+    }
+
+    pub fn lives(&self, player: &Player) -> Option<u8> {
+        match self.condition {
+            WinCondition::Lives(_) => Some(self.lives[self.scorer(player).index()]),
+            WinCondition::Points(_) | WinCondition::Time(_) => None,
+        }
+    }
+
+    pub fn is_eliminated(&self, player: &Player) -> bool {
+        self.lives(player) == Some(0)
+    }
+
+    /// The time left of a match with a time limit, `Duration::ZERO` in the sudden death
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        match self.condition {
+            WinCondition::Time(duration) => {
+                Some(duration.saturating_sub(now.duration_since(self.started)))
+            }
+            WinCondition::Points(_) | WinCondition::Lives(_) => None,
+        }
+    }
+
+    /// The share of the time limit that is left, from `0.0` to `1.0`
+    pub fn remaining_share(&self, now: Instant) -> Option<f32> {
+        match self.condition {
+            WinCondition::Time(duration) => self
+                .remaining(now)
+                .map(|remaining| remaining.as_secs_f32() / duration.as_secs_f32()),
+            WinCondition::Points(_) | WinCondition::Lives(_) => None,
+        }
+    }
+
+    /// Whether the time is up but nobody leads
+    pub fn is_sudden_death(&self, now: Instant) -> bool {
+        self.remaining(now) == Some(Duration::ZERO) && self.leader().is_none()
+    }
+
+    /// The only player or team with the most points
+    fn leader(&self) -> Option<Scorer> {
+        let scorers = self.scorers();
+        let most = scorers
+            .iter()
+            .map(|scorer| self.points[scorer.index()])
+            .max()?;
+        let mut leaders = scorers
+            .into_iter()
+            .filter(|scorer| self.points[scorer.index()] == most);
+        match (leaders.next(), leaders.next()) {
+            (Some(leader), None) => Some(leader),
+            _ => None,
+        }
+    }
+
+    pub fn winner(&self, now: Instant) -> Option<Scorer> {
+        match self.condition {
+            WinCondition::Time(_) if self.remaining(now) == Some(Duration::ZERO) => self.leader(),
+            WinCondition::Time(_) => None,
+            WinCondition::Points(_) => {
+                let needed = self.rules.best_of.get() / 2 + 1;
+                self.scorers()
+                    .into_iter()
+                    .find(|scorer| self.sets[scorer.index()] >= needed)
+            }
+            WinCondition::Lives(_) => {
+                let mut standing = self
+                    .scorers()
+                    .into_iter()
+                    .filter(|scorer| self.lives[scorer.index()] > 0);
+                match (standing.next(), standing.next()) {
+                    (Some(winner), None) => Some(winner),
+                    _ => None,
+                }
+            }
         }
     }
 
+    /// The points, or the lives when playing with lives, of every player or team.
+    /// When playing more than one set the won sets follow
     pub fn fmt_score(&self) -> String {
-        format!("{}:{}", self.player_1_points, self.player_2_points)
+        let fmt = |values: &[u8; 4]| {
+            self.scorers()
+                .iter()
+                .map(|scorer| values[scorer.index()].to_string())
+                .collect::<Vec<_>>()
+                .join(":")
+        };
+        match self.condition {
+            WinCondition::Points(_) if self.rules.best_of.get() > 1 => {
+                format!("{}, sets {}", fmt(&self.points), fmt(&self.sets))
+            }
+            WinCondition::Points(_) | WinCondition::Time(_) => fmt(&self.points),
+            WinCondition::Lives(_) => fmt(&self.lives),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoring() {
+        let points = WinCondition::Points(NonZero::new(2).unwrap());
+        let mut duel = GameState::new(2, false, points);
+        duel.score_goal(&Player::Player2, None);
+        duel.score_goal(&Player::Player1, Some(Player::Player1));
+        assert_eq!(duel.fmt_score(), "1:1");
+
+        let mut four = GameState::new(4, false, points);
+        let result = four.score_goal(&Player::Player3, Some(Player::Player2));
+        assert_eq!(
+            result,
+            GoalResult::Scored(vec![Scorer::Player(Player::Player2)])
+        );
+        four.score_goal(&Player::Player4, Some(Player::Player4));
+        assert_eq!(four.fmt_score(), "1:2:1:0");
+
+        assert_eq!(
+            four.winner(Instant::now()),
+            Some(Scorer::Player(Player::Player2))
+        );
+
+        let mut teams = GameState::new(4, true, points);
+        teams.score_goal(&Player::Player2, Some(Player::Player3));
+        teams.score_goal(&Player::Player1, Some(Player::Player3));
+        assert_eq!(teams.fmt_score(), "1:1");
+        assert_eq!(teams.winner(Instant::now()), None);
+        teams.score_goal(&Player::Player4, Some(Player::Player1));
+        assert_eq!(teams.winner(Instant::now()), Some(Scorer::Team(1)));
+    }
+
+    #[test]
+    fn test_lives() {
+        let lives = WinCondition::Lives(NonZero::new(2).unwrap());
+        let mut state = GameState::new(3, false, lives);
+        assert_eq!(
+            state.score_goal(&Player::Player2, Some(Player::Player1)),
+            GoalResult::LostLife(Scorer::Player(Player::Player2))
+        );
+        assert_eq!(
+            state.score_goal(&Player::Player2, None),
+            GoalResult::Eliminated(Scorer::Player(Player::Player2))
+        );
+        assert!(state.is_eliminated(&Player::Player2));
+        assert_eq!(state.fmt_score(), "2:0:2");
+        assert_eq!(state.winner(Instant::now()), None);
+
+        state.score_goal(&Player::Player1, None);
+        state.score_goal(&Player::Player1, None);
+        assert_eq!(
+            state.winner(Instant::now()),
+            Some(Scorer::Player(Player::Player3))
+        );
+        assert_eq!(state.lives(&Player::Player3), Some(2));
+    }
+
+    #[test]
+    fn test_sets() {
+        let rules = SetRules {
+            win_by_two: true,
+            cap: NonZero::new(5),
+            best_of: NonZero::new(3).unwrap(),
+        };
+        let points = WinCondition::Points(NonZero::new(3).unwrap());
+        let mut state = GameState::new(2, false, points).with_rules(rules);
+        let mut score = |player: Player, points: usize| {
+            let conceded = match player {
+                Player::Player1 => Player::Player2,
+                _ => Player::Player1,
+            };
+            (0..points)
+                .map(|_| state.score_goal(&conceded, Some(player)))
+                .last()
+                .unwrap()
+        };
+
+        // Deuce, a lead of one isn't enough
+        score(Player::Player1, 2);
+        score(Player::Player2, 2);
+        assert!(matches!(score(Player::Player1, 1), GoalResult::Scored(_)));
+        assert_eq!(
+            score(Player::Player1, 1),
+            GoalResult::WonSet(Scorer::Player(Player::Player1))
+        );
+
+        // The cap ends the set without a lead of two
+        score(Player::Player1, 2);
+        score(Player::Player2, 2);
+        score(Player::Player1, 1);
+        score(Player::Player2, 2);
+        assert!(matches!(score(Player::Player1, 1), GoalResult::Scored(_)));
+        assert_eq!(
+            score(Player::Player1, 1),
+            GoalResult::WonMatch(Scorer::Player(Player::Player1))
+        );
+        // 4:4 -> 5:4 wins the set at the cap, and with it the match
+        assert_eq!(state.fmt_score(), "5:4, sets 2:0");
+        assert_eq!(
+            state.winner(Instant::now()),
+            Some(Scorer::Player(Player::Player1))
+        );
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut state = GameState::new(2, false, WinCondition::Time(Duration::from_secs(60)));
+        let at = |secs: u64| state.started + Duration::from_secs(secs);
+        let (half, end, overtime) = (at(30), at(60), at(75));
+
+        state.score_goal(&Player::Player1, Some(Player::Player2));
+        assert_eq!(state.remaining_share(half), Some(0.5));
+        assert_eq!(state.winner(half), None);
+
+        state.score_goal(&Player::Player2, Some(Player::Player1));
+        assert_eq!(state.remaining(end), Some(Duration::ZERO));
+        assert!(state.is_sudden_death(end));
+        assert_eq!(state.winner(end), None);
+
+        state.score_goal(&Player::Player1, None);
+        assert!(!state.is_sudden_death(overtime));
+        assert_eq!(
+            state.winner(overtime),
+            Some(Scorer::Player(Player::Player2))
+        );
     }
 }
@@ -0,0 +1,65 @@
+use crate::{color::Rgb, voxelbox::Draw};
+
+use super::collision::Bounds;
+
+/// The remaining time of a match, shown as a bar along the top back edge of the cube
+/// shrinking towards the left wall. During the sudden death the full bar turns red
+pub struct TimeBar<'a> {
+    bounds: &'a Bounds,
+    /// The share of the time that is left, from `0.0` to `1.0`
+    remaining: f32,
+    sudden_death: bool,
+}
+
+impl<'a> TimeBar<'a> {
+    pub const fn new(bounds: &'a Bounds, remaining: f32, sudden_death: bool) -> Self {
+        Self {
+            bounds,
+            remaining,
+            sudden_death,
+        }
+    }
+}
+
+impl Draw for TimeBar<'_> {
+    fn color(&self) -> Rgb {
+        if self.sudden_death {
+            Rgb::red()
+        } else {
+            Rgb::gray()
+        }
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        let width = self.bounds.width as f32;
+        let length = if self.sudden_death {
+            width
+        } else {
+            (self.remaining.clamp(0.0, 1.0) * width).ceil()
+        };
+        let z = self.bounds.depth as usize - 1;
+
+        (0..length as usize).map(|x| (x, 0, z)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_bar() {
+        let bounds = Bounds::default();
+        let full = TimeBar::new(&bounds, 1.0, false).draw();
+        assert_eq!(full.len(), bounds.width as usize);
+        assert_eq!(full[0], (0, 0, bounds.depth as usize - 1));
+
+        assert_eq!(TimeBar::new(&bounds, 0.5, false).draw().len(), 10);
+        assert_eq!(TimeBar::new(&bounds, 0.01, false).draw().len(), 1);
+        assert!(TimeBar::new(&bounds, 0.0, false).draw().is_empty());
+
+        let sudden_death = TimeBar::new(&bounds, 0.0, true);
+        assert_eq!(sudden_death.draw().len(), bounds.width as usize);
+        assert_eq!(sudden_death.color(), Rgb::red());
+    }
+}
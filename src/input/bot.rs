@@ -0,0 +1,280 @@
+use crate::{
+    game::{
+        ball::Ball,
+        collision::Bounds,
+        player::{Player, PlayerSite},
+    },
+    random,
+};
+use rand::{rngs::StdRng, Rng};
+use std::time::{Duration, Instant};
+
+use super::{Deflection, GameInput, Normalized};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+/// How well a bot plays
+struct Skill {
+    /// The time between two looks at the balls
+    reaction: Duration,
+    /// The number of bounces off the arena the bot foresees
+    prediction: u8,
+    /// The bot misses its target by up to this many voxels along both axes
+    aim_error: f32,
+    /// The highest velocity of the paddle as a fraction of its maximum speed
+    max_speed: f32,
+}
+
+impl Difficulty {
+    const fn skill(&self) -> Skill {
+        match self {
+            Self::Easy => Skill {
+                reaction: Duration::from_millis(500),
+                prediction: 0,
+                aim_error: 4.0,
+                max_speed: 0.5,
+            },
+            Self::Medium => Skill {
+                reaction: Duration::from_millis(300),
+                prediction: 1,
+                aim_error: 3.0,
+                max_speed: 0.5,
+            },
+            Self::Hard => Skill {
+                reaction: Duration::from_millis(80),
+                prediction: 4,
+                aim_error: 0.3,
+                max_speed: 1.0,
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Self::Easy),
+            "medium" => Ok(Self::Medium),
+            "hard" => Ok(Self::Hard),
+            _ => Err(format!("{s} isn't a difficulty, use easy, medium or hard")),
+        }
+    }
+}
+
+/// A computer-controlled player following the ball that comes towards its wall
+#[derive(Debug)]
+pub struct Bot {
+    difficulty: Difficulty,
+    deflection: Deflection,
+    /// Where the bot wants its paddle on the plane of its wall
+    target: Option<(f32, f32)>,
+    last_look: Option<Instant>,
+    /// The error of the current target, it changes when the followed ball changes its direction
+    aim_error: (f32, f32),
+    followed: Option<(i8, i8, i8)>,
+}
+
+impl Bot {
+    /// The distance to the target in voxels from which on the bot moves at full speed
+    const FULL_SPEED_DISTANCE: f32 = 3.0;
+
+    pub const fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            deflection: Deflection { x: 0.0, y: 0.0 },
+            target: None,
+            last_look: None,
+            aim_error: (0.0, 0.0),
+            followed: None,
+        }
+    }
+
+    /// Looks at the balls if the bot can react again and moves the paddle of `player`
+    /// towards its target.
+    /// The bot follows the nearest ball approaching its wall and returns to the center otherwise
+    pub fn observe<'a>(
+        &mut self,
+        player: &Player,
+        balls: impl IntoIterator<Item = &'a Ball>,
+        now: Instant,
+    ) {
+        let skill = self.difficulty.skill();
+        let reacts = self
+            .last_look
+            .is_none_or(|last_look| now.duration_since(last_look) >= skill.reaction);
+        if reacts {
+            self.last_look = Some(now);
+            self.target = Some(self.look(player, balls, &skill));
+        }
+
+        let (_, y, z) = player.full_position();
+        let Some((target_y, target_z)) = self.target else {
+            return;
+        };
+        let deflection = |target: f32, position: u8| {
+            ((target - position as f32) / Self::FULL_SPEED_DISTANCE)
+                .clamp(-skill.max_speed, skill.max_speed)
+        };
+        self.deflection = Deflection {
+            x: deflection(target_z, z),
+            y: deflection(target_y, y),
+        };
+    }
+
+    fn look<'a>(
+        &mut self,
+        player: &Player,
+        balls: impl IntoIterator<Item = &'a Ball>,
+        skill: &Skill,
+    ) -> (f32, f32) {
+        let site = player.site();
+        let bounds = player.bounds();
+        let Some(ball) = balls
+            .into_iter()
+            .filter(|ball| site.is_approached(ball.direction()))
+            .min_by_key(|ball| site.distance(bounds, ball.position))
+        else {
+            self.followed = None;
+            let (length_a, length_b) = site.plane(bounds);
+            return ((length_a / 2).into(), (length_b / 2).into());
+        };
+
+        if self.followed != Some(ball.direction()) {
+            self.followed = Some(ball.direction());
+            let error = |rng: &mut StdRng| rng.random_range(-skill.aim_error..=skill.aim_error);
+            self.aim_error = random::with_rng(|rng| (error(rng), error(rng)));
+        }
+
+        let (a, b) = predict(ball, site, bounds, skill.prediction);
+        (a as f32 + self.aim_error.0, b as f32 + self.aim_error.1)
+    }
+}
+
+/// Where the ball reaches the paddles in front of the wall at `site` on the plane of the wall,
+/// foreseeing at most `bounces` bounces off the arena. Without foreseeing the next bounce
+/// the prediction stops where the ball hits the arena
+fn predict(ball: &Ball, site: &PlayerSite, bounds: &Bounds, bounces: u8) -> (u8, u8) {
+    let padding = Ball::PADDING as i16;
+    let (length_a, length_b) = site.plane(bounds);
+    let (direction_a, direction_b) = site.project(ball.direction());
+    let (a, b) = site.project(ball.position);
+    let mut axes = [
+        (a as i16, direction_a as i16, length_a as i16),
+        (b as i16, direction_b as i16, length_b as i16),
+    ];
+
+    // The ball touches a paddle lying directly in front of the wall one layer before it
+    let steps = site
+        .distance(bounds, ball.position)
+        .saturating_sub(padding as u8 + 1);
+    let mut bounced = 0;
+    'steps: for _ in 0..steps {
+        for (position, direction, length) in &mut axes {
+            let next = *position + *direction;
+            if (padding..length.saturating_sub(padding)).contains(&next) {
+                continue;
+            }
+            if bounced == bounces {
+                break 'steps;
+            }
+            bounced += 1;
+            *direction = -*direction;
+        }
+        for (position, direction, _) in &mut axes {
+            *position += *direction;
+        }
+    }
+
+    let [(a, ..), (b, ..)] = axes;
+    (a as u8, b as u8)
+}
+
+impl GameInput for Bot {
+    /// The stick values leading to the deflection the bot decided on
+    fn normalized(&self) -> Normalized {
+        Normalized {
+            x: Some(-self.deflection.x),
+            y: Some(-self.deflection.y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{level::Level, state};
+    use std::num::NonZero;
+
+    fn ball(position: (u8, u8, u8), (x, y, z): (i8, i8, i8)) -> Ball {
+        let mut ball = Ball::default().with_direction((NonZero::new(x).unwrap(), y, z));
+        ball.position = position;
+        ball
+    }
+
+    #[test]
+    fn test_bot() {
+        let level = Level::default();
+        let player = level.player(state::Player::Player2);
+        let mut bot = Bot::new(Difficulty::Hard);
+        let now = Instant::now();
+        let later = |ms: u64| now + Duration::from_millis(ms);
+
+        // The paddle is at (10, 6), it follows a ball coming towards it
+        bot.observe(&player, [&ball((12, 4, 9), (1, 0, 0))], now);
+        assert!(bot.deflection().x > 0.8);
+        assert!(bot.deflection().y < -0.8);
+
+        // The bot doesn't notice the ball moving away before it can react again
+        bot.observe(&player, [&ball((12, 4, 9), (-1, 0, 0))], later(20));
+        assert!(bot.deflection().x > 0.8);
+
+        // A ball moving away is ignored, the paddle returns to the center
+        bot.observe(&player, [&ball((12, 4, 9), (-1, 0, 0))], later(100));
+        assert_eq!(bot.velocity().x, 0.0);
+        assert_eq!(bot.velocity().y, 0.0);
+
+        // The nearest approaching ball counts
+        let balls = [ball((6, 16, 6), (1, 0, 0)), ball((16, 2, 6), (1, 0, 0))];
+        bot.observe(&player, &balls, later(200));
+        assert_eq!(bot.deflection().y, -1.0);
+
+        // An easy bot moves slower
+        let mut easy = Bot::new(Difficulty::Easy);
+        easy.observe(&player, [&ball((12, 4, 9), (1, 0, 0))], now);
+        assert_eq!(easy.deflection().y, -0.5);
+    }
+
+    #[test]
+    fn test_prediction() {
+        let bounds = Bounds::default();
+        let right = PlayerSite::Right;
+
+        // Straight and diagonal towards the wall, the ball reaches the paddle at x 17
+        assert_eq!(
+            predict(&ball((5, 8, 6), (1, 0, 0)), &right, &bounds, 0),
+            (8, 6)
+        );
+        assert_eq!(
+            predict(&ball((5, 8, 6), (1, 1, 0)), &right, &bounds, 0),
+            (18, 6)
+        );
+
+        // Bouncing off the front and back of the arena
+        let bouncing = ball((5, 8, 6), (1, 0, 1));
+        assert_eq!(predict(&bouncing, &right, &bounds, 0), (8, 10));
+        assert_eq!(predict(&bouncing, &right, &bounds, 1), (8, 2));
+        assert_eq!(predict(&bouncing, &right, &bounds, 2), (8, 2));
+
+        // Players on the top and bottom wall see the x and z axis
+        let top = ball((10, 10, 6), (1, -1, 0));
+        assert_eq!(predict(&top, &PlayerSite::Top, &bounds, 0), (18, 6));
+    }
+}
@@ -1,9 +1,7 @@
-use std::time::Duration;
-
 use crate::positive::Positive;
-use gilrs::{Axis, Event, EventType, GamepadId};
+use gilrs::{Axis, Button, Event, EventType, GamepadId};
 
-use super::{ActivationTimes, GameInput, Movement};
+use super::{Deflection, GameInput, Navigation, Velocity};
 
 #[derive(Debug)]
 pub struct JoyStick {
@@ -14,10 +12,17 @@ pub struct JoyStick {
     sensitivity: Positive<f32>,
     latest_x: Option<f32>,
     latest_y: Option<f32>,
+    latest_trigger: Option<f32>,
+    /// Menu navigation since it was taken the last time
+    navigation: Vec<Navigation>,
 }
 
 impl JoyStick {
     const DEFAULT_DEADZONE: f32 = 0.15;
+    /// A stick moved beyond this value navigates a menu once
+    const NAVIGATION_THRESHOLD: f32 = 0.5;
+    /// Nobody takes the navigation during a match, older navigation is dropped beyond this
+    const MAX_NAVIGATION: usize = 16;
 
     const fn new(
         gamepad_id: GamepadId,
@@ -33,6 +38,8 @@ impl JoyStick {
             sensitivity,
             latest_x: None,
             latest_y: None,
+            latest_trigger: None,
+            navigation: Vec::new(),
         }
     }
 
@@ -48,25 +55,78 @@ impl JoyStick {
         Self::new(gamepad_id, sensitivity, own_controller, true)
     }
 
+    pub const fn new_player_3(gamepad_id: GamepadId, sensitivity: Positive<f32>) -> Self {
+        Self::new(gamepad_id, sensitivity, true, false)
+    }
+
+    pub const fn new_player_4(gamepad_id: GamepadId, sensitivity: Positive<f32>) -> Self {
+        Self::new(gamepad_id, sensitivity, true, true)
+    }
+
     pub fn add_event(&mut self, event: &Event) -> Option<(Axis, f32)> {
         if event.id != self.gamepad_id {
             return None;
         }
 
-        let EventType::AxisChanged(axis, strength, _) = event.event else {
-            return None;
+        let (axis, strength) = match event.event {
+            EventType::ButtonPressed(button, _) => {
+                let navigation = match button {
+                    Button::DPadUp => Navigation::Up,
+                    Button::DPadDown => Navigation::Down,
+                    Button::DPadLeft => Navigation::Left,
+                    Button::DPadRight => Navigation::Right,
+                    Button::South | Button::Start => Navigation::Confirm,
+                    _ => return None,
+                };
+                self.navigate(navigation);
+                return None;
+            }
+            EventType::AxisChanged(axis, strength, _) => (axis, strength),
+            EventType::ButtonChanged(button, value, _) => {
+                match (self.is_left_stick, button) {
+                    (true, Button::LeftTrigger2) | (false, Button::RightTrigger2) => {
+                        self.latest_trigger = Some(value);
+                    }
+                    _ => {}
+                }
+                return None;
+            }
+            _ => return None,
         };
 
-        match (self.is_left_stick, axis) {
-            (true, Axis::LeftStickX) => self.latest_x = Some(strength),
-            (true, Axis::LeftStickY) => self.latest_y = Some(strength),
-            (false, Axis::RightStickX) => self.latest_x = Some(strength),
-            (false, Axis::RightStickY) => self.latest_y = Some(strength),
+        let (latest, directions) = match (self.is_left_stick, axis) {
+            (true, Axis::LeftStickX) | (false, Axis::RightStickX) => {
+                (&mut self.latest_x, (Navigation::Left, Navigation::Right))
+            }
+            (true, Axis::LeftStickY) | (false, Axis::RightStickY) => {
+                (&mut self.latest_y, (Navigation::Down, Navigation::Up))
+            }
             _ => return None,
+        };
+        let before = latest.replace(strength).unwrap_or(0.0);
+        if before.abs() < Self::NAVIGATION_THRESHOLD && strength.abs() >= Self::NAVIGATION_THRESHOLD
+        {
+            self.navigate(if strength < 0.0 {
+                directions.0
+            } else {
+                directions.1
+            });
         }
         Some((axis, strength))
     }
 
+    fn navigate(&mut self, navigation: Navigation) {
+        if self.navigation.len() == Self::MAX_NAVIGATION {
+            self.navigation.remove(0);
+        }
+        self.navigation.push(navigation);
+    }
+
+    /// The menu navigation of the gamepad since the last call
+    pub fn take_navigation(&mut self) -> Vec<Navigation> {
+        std::mem::take(&mut self.navigation)
+    }
+
     const fn x_value(&self) -> Option<f32> {
         self.latest_x
     }
@@ -77,28 +137,6 @@ impl JoyStick {
 }
 
 impl GameInput for JoyStick {
-    const MIN_TIME: Duration = Duration::from_millis(110);
-    const MAX_TIME: Duration = Duration::from_millis(350);
-
-    fn activation_times(&self) -> ActivationTimes {
-        let sensitivity = self.sensitivity.value();
-        let ms_range = (Self::MAX_TIME - Self::MIN_TIME).as_millis() as f32;
-
-        let compute_time = |normalized: Option<f32>| {
-            normalized.map(|n| {
-                let extra_ms = ms_range * (1.0 - n.abs().powf(sensitivity));
-                let extra_ms = extra_ms.round() as u64;
-                Self::MIN_TIME + Duration::from_millis(extra_ms)
-            })
-        };
-
-        let normalized = self.normalized();
-        ActivationTimes {
-            x: compute_time(normalized.x),
-            y: compute_time(normalized.y),
-        }
-    }
-
     fn normalized(&self) -> super::Normalized {
         let deadzone = self.deadzone;
 
@@ -119,16 +157,22 @@ impl GameInput for JoyStick {
         }
     }
 
-    fn movement(&self) -> Movement {
-        let normalized = self.normalized();
+    fn push(&self) -> Option<f32> {
+        self.latest_trigger
+    }
 
-        let x = -normalized.x.unwrap_or(0.0).signum() as i16;
-        let y = -normalized.y.unwrap_or(0.0).signum() as i16;
+    fn inverts_x(&self) -> bool {
+        self.invert_x
+    }
+
+    fn velocity(&self) -> Velocity {
+        let sensitivity = self.sensitivity.value();
+        let Deflection { x, y } = self.deflection();
+        let velocity = |d: f32| d.signum() * d.abs().powf(sensitivity);
 
-        if self.invert_x {
-            Movement { x: -x, y }
-        } else {
-            Movement { x, y }
+        Velocity {
+            x: velocity(x),
+            y: velocity(y),
         }
     }
 }
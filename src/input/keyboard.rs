@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    os::fd::AsRawFd,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use termios::{tcsetattr, Termios, ECHO, ICANON, ISIG, TCSANOW, VMIN, VTIME};
+
+use super::{GameInput, Navigation, Normalized};
+
+/// Ctrl-C, signals are turned off while the keys are read
+const CTRL_C: u8 = 3;
+const ESCAPE: u8 = 0x1b;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    W,
+    A,
+    S,
+    D,
+    Space,
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Quit,
+}
+
+/// Turns the bytes read from the terminal into keys, other keys are skipped.
+/// Arrow keys arrive as escape sequences, `ESC [ A` or `ESC O A` for up
+fn parse(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut bytes = bytes.iter().copied();
+    while let Some(byte) = bytes.next() {
+        let key = match byte.to_ascii_lowercase() {
+            b'w' => Key::W,
+            b'a' => Key::A,
+            b's' => Key::S,
+            b'd' => Key::D,
+            b' ' => Key::Space,
+            b'\r' | b'\n' => Key::Enter,
+            CTRL_C => Key::Quit,
+            ESCAPE => match (bytes.next(), bytes.next()) {
+                (Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+                (Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+                (Some(b'[' | b'O'), Some(b'C')) => Key::Right,
+                (Some(b'[' | b'O'), Some(b'D')) => Key::Left,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// The keys one player uses on a shared keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    /// WASD to move and space to confirm
+    Wasd,
+    /// The arrow keys to move and enter to confirm
+    Arrows,
+}
+
+impl KeyboardLayout {
+    /// The keys for up, down, left, right and confirm
+    const fn keys(&self) -> [Key; 5] {
+        match self {
+            Self::Wasd => [Key::W, Key::S, Key::A, Key::D, Key::Space],
+            Self::Arrows => [Key::Up, Key::Down, Key::Left, Key::Right, Key::Enter],
+        }
+    }
+}
+
+/// The keys read from the terminal, shared by the reading thread and the players
+#[derive(Debug, Default)]
+struct Keys {
+    /// When every key was pressed the last time
+    pressed: HashMap<Key, Instant>,
+    /// The keys pressed since the players took their navigation
+    presses: Vec<Key>,
+}
+
+impl Keys {
+    /// Nobody takes the navigation during a match, older presses are dropped beyond this
+    const MAX_PRESSES: usize = 32;
+
+    fn press(&mut self, key: Key, now: Instant) {
+        self.pressed.insert(key, now);
+        if self.presses.len() == Self::MAX_PRESSES {
+            self.presses.remove(0);
+        }
+        self.presses.push(key);
+    }
+}
+
+/// Reads the keys pressed in the terminal without waiting for enter and without echoing them.
+/// The terminal is restored when it is dropped or the game is quit with Ctrl-C
+pub struct Terminal {
+    original: Termios,
+    keys: Arc<Mutex<Keys>>,
+}
+
+impl Terminal {
+    pub fn open() -> io::Result<Self> {
+        let stdin = io::stdin().as_raw_fd();
+        let original = Termios::from_fd(stdin)?;
+        let mut raw = original;
+        raw.c_lflag &= !(ICANON | ECHO | ISIG);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        tcsetattr(stdin, TCSANOW, &raw)?;
+
+        let keys = Arc::new(Mutex::new(Keys::default()));
+        let shared = Arc::clone(&keys);
+        thread::spawn(move || {
+            let mut buffer = [0; 64];
+            let mut input = io::stdin().lock();
+            while let Ok(read @ 1..) = input.read(&mut buffer) {
+                for key in parse(&buffer[..read]) {
+                    if key == Key::Quit {
+                        let _ = tcsetattr(stdin, TCSANOW, &original);
+                        std::process::exit(0);
+                    }
+                    if let Ok(mut keys) = shared.lock() {
+                        keys.press(key, Instant::now());
+                    }
+                }
+            }
+        });
+
+        Ok(Self { original, keys })
+    }
+
+    /// The input of a player using `layout`, see [`GameInput::inverts_x`] for `invert_x`
+    pub fn keyboard(&self, layout: KeyboardLayout, invert_x: bool) -> Keyboard {
+        Keyboard {
+            layout,
+            invert_x,
+            keys: Arc::clone(&self.keys),
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = tcsetattr(io::stdin().as_raw_fd(), TCSANOW, &self.original);
+    }
+}
+
+/// A player on a shared keyboard, the keys move the paddle at full speed
+#[derive(Debug)]
+pub struct Keyboard {
+    layout: KeyboardLayout,
+    invert_x: bool,
+    keys: Arc<Mutex<Keys>>,
+}
+
+impl Keyboard {
+    /// Terminals don't report released keys, a key counts as held this long after a press.
+    /// A held key is pressed again by the key repeat of the system, which only starts after
+    /// its delay of up to 660ms on X11, so the hold lasts longer to keep the paddle moving
+    const HOLD: Duration = Duration::from_millis(700);
+
+    fn normalized_at(&self, now: Instant) -> Normalized {
+        let Ok(keys) = self.keys.lock() else {
+            return Normalized { x: None, y: None };
+        };
+        let held = |key: Key| {
+            keys.pressed
+                .get(&key)
+                .filter(|&&pressed| now.duration_since(pressed) < Self::HOLD)
+        };
+        // The key pressed last wins if both directions are held
+        let axis = |negative: Key, positive: Key| match (held(negative), held(positive)) {
+            (Some(negative), Some(positive)) => Some(if positive > negative { 1.0 } else { -1.0 }),
+            (Some(_), None) => Some(-1.0),
+            (None, Some(_)) => Some(1.0),
+            (None, None) => None,
+        };
+
+        let [up, down, left, right, _] = self.layout.keys();
+        Normalized {
+            x: axis(left, right),
+            y: axis(down, up),
+        }
+    }
+
+    /// The menu navigation with the keys of the layout since the last call
+    pub fn take_navigation(&mut self) -> Vec<Navigation> {
+        let Ok(mut keys) = self.keys.lock() else {
+            return Vec::new();
+        };
+        let [up, down, left, right, confirm] = self.layout.keys();
+        let mut navigation = Vec::new();
+        keys.presses.retain(|&key| {
+            let direction = match key {
+                _ if key == up => Navigation::Up,
+                _ if key == down => Navigation::Down,
+                _ if key == left => Navigation::Left,
+                _ if key == right => Navigation::Right,
+                _ if key == confirm => Navigation::Confirm,
+                _ => return true,
+            };
+            navigation.push(direction);
+            false
+        });
+        navigation
+    }
+}
+
+impl GameInput for Keyboard {
+    fn normalized(&self) -> Normalized {
+        self.normalized_at(Instant::now())
+    }
+
+    fn inverts_x(&self) -> bool {
+        self.invert_x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(b"wAx d\n"),
+            [Key::W, Key::A, Key::Space, Key::D, Key::Enter]
+        );
+        assert_eq!(
+            parse(b"\x1b[A\x1bOD\x1b[Zs\x03"),
+            [Key::Up, Key::Left, Key::S, Key::Quit]
+        );
+    }
+
+    #[test]
+    fn test_keyboard() {
+        let keys = Arc::new(Mutex::new(Keys::default()));
+        let keyboard = |layout| Keyboard {
+            layout,
+            invert_x: false,
+            keys: Arc::clone(&keys),
+        };
+        let mut wasd = keyboard(KeyboardLayout::Wasd);
+        let mut arrows = keyboard(KeyboardLayout::Arrows);
+        let now = Instant::now();
+        let later = |ms: u64| now + Duration::from_millis(ms);
+
+        {
+            let mut keys = keys.lock().unwrap();
+            keys.press(Key::A, now);
+            keys.press(Key::W, now);
+            keys.press(Key::Up, now);
+            keys.press(Key::D, later(100));
+        }
+        let normalized = wasd.normalized_at(later(100));
+        assert_eq!((normalized.x, normalized.y), (Some(1.0), Some(1.0)));
+        let normalized = wasd.normalized_at(later(750));
+        assert_eq!((normalized.x, normalized.y), (Some(1.0), None));
+        assert_eq!(arrows.normalized_at(later(750)).y, None);
+
+        // Every player takes only the keys of its own layout
+        assert_eq!(arrows.take_navigation(), [Navigation::Up]);
+        assert_eq!(
+            wasd.take_navigation(),
+            [Navigation::Left, Navigation::Up, Navigation::Right]
+        );
+        assert!(wasd.take_navigation().is_empty());
+    }
+}
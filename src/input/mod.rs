@@ -1,8 +1,15 @@
+mod bot;
 mod joystick;
+mod keyboard;
+mod network;
 
+pub use bot::*;
 pub use joystick::*;
+pub use keyboard::*;
+pub use network::*;
 
-use std::time::Duration;
+use crate::game::{ball::Ball, player::Player};
+use std::time::Instant;
 
 #[derive(Debug)]
 pub struct TwoDimensional<T> {
@@ -10,36 +17,151 @@ pub struct TwoDimensional<T> {
     pub y: T,
 }
 
-pub type ActivationTimes = TwoDimensional<Option<Duration>>;
+/// Moving through a menu with the D-pad or a stick and confirming with a button
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Navigation {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+}
+
 pub type Normalized = TwoDimensional<Option<f32>>;
-pub type Movement = TwoDimensional<i16>;
+/// How far the input points in the direction of the paddle axes, from `-1.0` to `1.0`
+pub type Deflection = TwoDimensional<f32>;
+/// The wanted velocity of the paddle as a fraction of its maximum speed, from `-1.0` to `1.0`
+pub type Velocity = TwoDimensional<f32>;
 
 pub trait GameInput {
-    const MIN_TIME: Duration;
-    const MAX_TIME: Duration = Self::MIN_TIME;
     fn normalized(&self) -> Normalized;
-    fn activation_times(&self) -> ActivationTimes {
-        let ms_range = (Self::MAX_TIME - Self::MIN_TIME).as_millis() as f32;
+    /// How far the paddle should be pushed into the arena, from `0.0` to `1.0`
+    fn push(&self) -> Option<f32> {
+        None
+    }
+    /// Players facing the other way invert the x axis, like the gamepads of player 2 and 4
+    fn inverts_x(&self) -> bool {
+        false
+    }
+    fn deflection(&self) -> Deflection {
+        let normalized = self.normalized();
+        let x = -normalized.x.unwrap_or(0.0);
 
-        let compute_time = |normalized: Option<f32>| {
-            normalized.map(|n| {
-                let extra_ms = (ms_range * (1.0 - n)).round() as u64;
-                Self::MIN_TIME + Duration::from_millis(extra_ms)
-            })
-        };
+        Deflection {
+            x: if self.inverts_x() { -x } else { x },
+            y: -normalized.y.unwrap_or(0.0),
+        }
+    }
+    fn velocity(&self) -> Velocity {
+        self.deflection()
+    }
+}
 
-        let normalized = self.normalized();
-        ActivationTimes {
-            x: compute_time(normalized.x),
-            y: compute_time(normalized.x),
+/// Where the people playing give their input
+#[derive(Clone, Copy)]
+pub enum Controllers<'a> {
+    Gamepads(&'a [gilrs::GamepadId]),
+    /// The first player uses WASD and the second one the arrow keys of the terminal
+    Keyboard(&'a Terminal),
+    /// Every player is controlled from another device over the network
+    Network(&'a Server),
+}
+
+impl Controllers<'_> {
+    /// The number of people who can play with an own controller
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Gamepads(gamepads) => gamepads.len(),
+            Self::Keyboard(_) => 2,
+            Self::Network(server) => server.controllers(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The input of a player, either a person with a gamepad, keyboard or remote controller or a bot
+pub enum PlayerInput {
+    JoyStick(JoyStick),
+    Keyboard(Keyboard),
+    Remote(Remote),
+    Bot(Bot),
+}
+
+impl PlayerInput {
+    pub fn add_event(&mut self, event: &gilrs::Event) {
+        if let Self::JoyStick(joystick) = self {
+            joystick.add_event(event);
+        }
+    }
+
+    /// The menu navigation since the last call, remote controllers and bots don't navigate
+    pub fn take_navigation(&mut self) -> Vec<Navigation> {
+        match self {
+            Self::JoyStick(joystick) => joystick.take_navigation(),
+            Self::Keyboard(keyboard) => keyboard.take_navigation(),
+            Self::Remote(_) | Self::Bot(_) => Vec::new(),
+        }
+    }
+
+    /// Lets a bot look at the game before its input is read
+    pub fn observe<'a>(
+        &mut self,
+        player: &Player,
+        balls: impl IntoIterator<Item = &'a Ball>,
+        now: Instant,
+    ) {
+        if let Self::Bot(bot) = self {
+            bot.observe(player, balls, now);
+        }
+    }
+}
+
+impl GameInput for PlayerInput {
+    fn normalized(&self) -> Normalized {
+        match self {
+            Self::JoyStick(joystick) => joystick.normalized(),
+            Self::Keyboard(keyboard) => keyboard.normalized(),
+            Self::Remote(remote) => remote.normalized(),
+            Self::Bot(bot) => bot.normalized(),
+        }
+    }
+
+    fn push(&self) -> Option<f32> {
+        match self {
+            Self::JoyStick(joystick) => joystick.push(),
+            Self::Keyboard(keyboard) => keyboard.push(),
+            Self::Remote(remote) => remote.push(),
+            Self::Bot(bot) => bot.push(),
+        }
+    }
+
+    fn inverts_x(&self) -> bool {
+        match self {
+            Self::JoyStick(joystick) => joystick.inverts_x(),
+            Self::Keyboard(keyboard) => keyboard.inverts_x(),
+            Self::Remote(remote) => remote.inverts_x(),
+            Self::Bot(bot) => bot.inverts_x(),
         }
     }
-    fn movement(&self) -> Movement {
-        let normalized = self.normalized();
 
-        let x = -normalized.x.unwrap_or(0.0).signum() as i16;
-        let y = -normalized.x.unwrap_or(0.0).signum() as i16;
+    fn deflection(&self) -> Deflection {
+        match self {
+            Self::JoyStick(joystick) => joystick.deflection(),
+            Self::Keyboard(keyboard) => keyboard.deflection(),
+            Self::Remote(remote) => remote.deflection(),
+            Self::Bot(bot) => bot.deflection(),
+        }
+    }
 
-        Movement { x, y }
+    fn velocity(&self) -> Velocity {
+        match self {
+            Self::JoyStick(joystick) => joystick.velocity(),
+            Self::Keyboard(keyboard) => keyboard.velocity(),
+            Self::Remote(remote) => remote.velocity(),
+            Self::Bot(bot) => bot.velocity(),
+        }
     }
 }
@@ -0,0 +1,360 @@
+use crate::log;
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{GameInput, Normalized};
+
+/// A datagram sent by a remote controller, every datagram holds a single message as text
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Message {
+    /// `join` takes the first free controller, `join 2` the second one
+    Join(Option<usize>),
+    /// `stick <x> <y> [push]`, the stick from `-1.0` to `1.0` and the trigger from `0.0` to `1.0`
+    Stick { x: f32, y: f32, push: f32 },
+    /// `leave` frees the controller for someone else
+    Leave,
+}
+
+impl std::str::FromStr for Message {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        // NaN and infinity would pass the clamping and move the paddle to an edge
+        let number = |word: Option<&str>| {
+            word.map(|word| {
+                word.parse::<f32>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| format!("{word} isn't a number"))
+            })
+        };
+        match words.next() {
+            Some("join") => match words.next() {
+                Some(controller) => controller
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&controller| controller > 0)
+                    .map(|controller| Self::Join(Some(controller - 1)))
+                    .ok_or_else(|| format!("{controller} isn't a controller")),
+                None => Ok(Self::Join(None)),
+            },
+            Some("stick") => {
+                let (Some(x), Some(y)) = (number(words.next()), number(words.next())) else {
+                    return Err(String::from("stick needs an x and y value"));
+                };
+                Ok(Self::Stick {
+                    x: x?.clamp(-1.0, 1.0),
+                    y: y?.clamp(-1.0, 1.0),
+                    push: number(words.next()).unwrap_or(Ok(0.0))?.clamp(0.0, 1.0),
+                })
+            }
+            Some("leave") => Ok(Self::Leave),
+            _ => Err(format!(
+                "{} isn't a message, use join, stick or leave",
+                s.trim()
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Session {
+    address: SocketAddr,
+    last_message: Instant,
+    stick: (f32, f32),
+    push: f32,
+}
+
+/// The remote controllers, a controller belongs to the address that joined it until it leaves
+/// or stays silent for longer than the timeout
+#[derive(Debug)]
+struct Sessions {
+    controllers: Vec<Option<Session>>,
+}
+
+impl Sessions {
+    /// A controller is freed if its client sends nothing for this long
+    const TIMEOUT: Duration = Duration::from_secs(3);
+
+    fn new(controllers: usize) -> Self {
+        Self {
+            controllers: (0..controllers).map(|_| None).collect(),
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        for (index, controller) in self.controllers.iter_mut().enumerate() {
+            if controller
+                .as_ref()
+                .is_some_and(|session| now.duration_since(session.last_message) >= Self::TIMEOUT)
+            {
+                *controller = None;
+                log!(Warning, "Remote controller {} timed out", index + 1);
+            }
+        }
+    }
+
+    /// The live session of a controller
+    fn session(&self, controller: usize, now: Instant) -> Option<&Session> {
+        self.controllers
+            .get(controller)?
+            .as_ref()
+            .filter(|session| now.duration_since(session.last_message) < Self::TIMEOUT)
+    }
+
+    /// Handles a message of the client at `from`, returns the answer to send back
+    fn handle(&mut self, message: &str, from: SocketAddr, now: Instant) -> Option<String> {
+        self.expire(now);
+        let own = self.controllers.iter().position(|session| {
+            session
+                .as_ref()
+                .is_some_and(|session| session.address == from)
+        });
+        let message = match message.parse() {
+            Ok(message) => message,
+            Err(err) => return Some(format!("error {err}")),
+        };
+
+        match message {
+            Message::Join(wanted) => {
+                let free = |index: &usize| self.controllers[*index].is_none();
+                let controller = match wanted {
+                    _ if own.is_some() && (wanted.is_none() || wanted == own) => own,
+                    Some(wanted) if wanted >= self.controllers.len() => {
+                        return Some(format!("error controller {} doesn't exist", wanted + 1));
+                    }
+                    Some(wanted) => Some(wanted).filter(free),
+                    None => (0..self.controllers.len()).find(free),
+                };
+                let Some(controller) = controller else {
+                    return Some(String::from("taken"));
+                };
+                if let Some(own) = own.filter(|&own| own != controller) {
+                    self.controllers[own] = None;
+                }
+                // The controller is either free or already the own one
+                match &mut self.controllers[controller] {
+                    Some(session) => session.last_message = now,
+                    None => {
+                        log!(
+                            Log,
+                            "Remote controller {} joined from {}",
+                            controller + 1,
+                            from
+                        );
+                        self.controllers[controller] = Some(Session {
+                            address: from,
+                            last_message: now,
+                            stick: (0.0, 0.0),
+                            push: 0.0,
+                        });
+                    }
+                }
+                Some(format!("joined {}", controller + 1))
+            }
+            Message::Stick { x, y, push } => {
+                let Some(session) = own.and_then(|own| self.controllers[own].as_mut()) else {
+                    return Some(String::from("error join first"));
+                };
+                session.last_message = now;
+                session.stick = (x, y);
+                session.push = push;
+                None
+            }
+            Message::Leave => {
+                let own = own?;
+                self.controllers[own] = None;
+                log!(Log, "Remote controller {} left", own + 1);
+                Some(String::from("left"))
+            }
+        }
+    }
+}
+
+/// Receives the sticks of remote controllers, e.g. phones of visitors, over UDP.
+/// A client joins a controller with `join`, sends `stick <x> <y> [push]` at least every second
+/// and ends with `leave`
+pub struct Server {
+    address: SocketAddr,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl Server {
+    pub fn bind(address: impl ToSocketAddrs, controllers: usize) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        let address = socket.local_addr()?;
+        let sessions = Arc::new(Mutex::new(Sessions::new(controllers)));
+        let shared = Arc::clone(&sessions);
+        thread::spawn(move || {
+            let mut buffer = [0; 512];
+            while let Ok((read, from)) = socket.recv_from(&mut buffer) {
+                let message = String::from_utf8_lossy(&buffer[..read]);
+                let Ok(mut sessions) = shared.lock() else {
+                    return;
+                };
+                if let Some(answer) = sessions.handle(&message, from, Instant::now()) {
+                    let _ = socket.send_to(answer.as_bytes(), from);
+                }
+            }
+        });
+
+        Ok(Self { address, sessions })
+    }
+
+    pub const fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The number of remote controllers
+    pub fn controllers(&self) -> usize {
+        self.sessions
+            .lock()
+            .map_or(0, |sessions| sessions.controllers.len())
+    }
+
+    /// The input of a remote controller, see [`GameInput::inverts_x`] for `invert_x`
+    pub fn remote(&self, controller: usize, invert_x: bool) -> Remote {
+        Remote {
+            controller,
+            invert_x,
+            sessions: Arc::clone(&self.sessions),
+        }
+    }
+}
+
+/// A player on a remote controller, without a client the paddle stands still
+#[derive(Debug)]
+pub struct Remote {
+    controller: usize,
+    invert_x: bool,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl Remote {
+    fn stick(&self) -> Option<(f32, f32, f32)> {
+        let sessions = self.sessions.lock().ok()?;
+        let session = sessions.session(self.controller, Instant::now())?;
+        Some((session.stick.0, session.stick.1, session.push))
+    }
+}
+
+impl GameInput for Remote {
+    fn normalized(&self) -> Normalized {
+        let stick = self.stick();
+        Normalized {
+            x: stick.map(|(x, _, _)| x),
+            y: stick.map(|(_, y, _)| y),
+        }
+    }
+
+    fn push(&self) -> Option<f32> {
+        self.stick().map(|(_, _, push)| push)
+    }
+
+    fn inverts_x(&self) -> bool {
+        self.invert_x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            "stick -3 0.5 1".parse(),
+            Ok(Message::Stick {
+                x: -1.0,
+                y: 0.5,
+                push: 1.0
+            })
+        );
+        assert_eq!(
+            "stick NaN 0".parse::<Message>(),
+            Err(String::from("NaN isn't a number"))
+        );
+        assert_eq!(
+            "stick 0 inf".parse::<Message>(),
+            Err(String::from("inf isn't a number"))
+        );
+        assert_eq!(
+            "stick 0 0 -inf".parse::<Message>(),
+            Err(String::from("-inf isn't a number"))
+        );
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut sessions = Sessions::new(2);
+        let phone: SocketAddr = "10.0.0.2:4000".parse().unwrap();
+        let laptop: SocketAddr = "10.0.0.3:4000".parse().unwrap();
+        let now = Instant::now();
+        let mut handle = |message: &str, from, after: u64| {
+            sessions.handle(message, from, now + Duration::from_secs(after))
+        };
+
+        assert_eq!(handle("stick 1 0", phone, 0).unwrap(), "error join first");
+        assert_eq!(handle("join 2", phone, 0).unwrap(), "joined 2");
+        assert_eq!(handle("join 2", laptop, 0).unwrap(), "taken");
+        assert_eq!(handle("join", laptop, 0).unwrap(), "joined 1");
+        assert_eq!(
+            handle("join 3", laptop, 0).unwrap(),
+            "error controller 3 doesn't exist"
+        );
+        assert!(handle("dance", laptop, 0)
+            .unwrap()
+            .starts_with("error dance"));
+        assert_eq!(handle("stick 0.5 -2 0.3\n", phone, 2), None);
+        assert_eq!(handle("leave", laptop, 2).unwrap(), "left");
+
+        let later = now + Duration::from_secs(2);
+        let session = sessions.session(1, later).unwrap();
+        assert_eq!((session.stick, session.push), ((0.5, -1.0), 0.3));
+        assert!(sessions.session(0, later).is_none());
+
+        // A silent phone loses its controller
+        assert!(sessions.session(1, later + Sessions::TIMEOUT).is_none());
+        let after_timeout = later + Sessions::TIMEOUT;
+        assert_eq!(
+            sessions.handle("join 2", laptop, after_timeout).unwrap(),
+            "joined 2"
+        );
+    }
+
+    #[test]
+    fn test_server() {
+        let server = Server::bind("127.0.0.1:0", 1).unwrap();
+        let remote = server.remote(0, true);
+        assert_eq!(remote.normalized().x, None);
+
+        // A scripted client, the answer to the second join shows the stick was handled
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.address()).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buffer = [0; 64];
+        let mut request = |message: &str| {
+            client.send(message.as_bytes()).unwrap();
+            let read = client.recv(&mut buffer).unwrap();
+            String::from_utf8_lossy(&buffer[..read]).into_owned()
+        };
+        assert_eq!(request("join"), "joined 1");
+        client.send(b"stick 0.5 1").unwrap();
+        assert_eq!(request("join"), "joined 1");
+
+        assert_eq!(remote.normalized().x, Some(0.5));
+        assert_eq!(remote.deflection().x, 0.5);
+        assert_eq!(remote.deflection().y, -1.0);
+        assert_eq!(remote.push(), Some(0.0));
+        assert_eq!(request("leave"), "left");
+        assert_eq!(remote.normalized().y, None);
+    }
+}
@@ -0,0 +1,25 @@
+//! A framework for games on the Voxelbox, a LED cube of 20x20x12 voxels, and 3D Pong built on it.
+//!
+//! - [`framework`] runs a [`framework::Game`], it keeps the time, polls the inputs and renders
+//! - [`voxelbox`] sends frames to the cube, everything implementing [`voxelbox::Draw`] can be drawn
+//! - [`color`], [`odd`] and [`positive`] hold the shared value types
+//! - [`random`] is the random number generator of the games, seedable to replay simulations
+//! - [`input`] reads gamepads, the keyboard and remote controllers and provides bots behind the
+//!   [`input::GameInput`] trait
+//! - [`game`] is the Pong game itself
+//! - [`snake`] and [`breakout`] are further games, a snake moving through the cube and a
+//!   brick-breaker built on the ball and paddles of Pong
+
+pub mod breakout;
+pub mod color;
+pub mod framework;
+pub mod game;
+pub mod input;
+pub mod log;
+pub mod macros;
+pub mod odd;
+pub mod positive;
+pub mod prelude;
+pub mod random;
+pub mod snake;
+pub mod voxelbox;
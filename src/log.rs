@@ -1,4 +1,9 @@
 use ansi_term::Color;
+use std::cell::Cell;
+
+thread_local! {
+    static MUTED: Cell<bool> = const { Cell::new(false) };
+}
 
 pub enum Severity {
     Critical,
@@ -19,9 +24,24 @@ impl Severity {
     }
 }
 
+/// Runs `f` without logging on this thread, e.g. to keep the output of a simulation clean
+pub fn muted<T>(f: impl FnOnce() -> T) -> T {
+    let muted = MUTED.replace(true);
+    let result = f();
+    MUTED.set(muted);
+    result
+}
+
+/// Whether [`crate::log!`] prints on this thread, see [`muted`]
+pub fn enabled() -> bool {
+    !MUTED.get()
+}
+
 #[macro_export]
 macro_rules! log {
     ($severity:ident, $($arg:tt)*) => {{
-        print!("{} {}\n", $crate::Severity::$severity.prefix(), format!($($arg)*));
+        if $crate::log::enabled() {
+            print!("{} {}\n", $crate::log::Severity::$severity.prefix(), format!($($arg)*));
+        }
     }};
 }
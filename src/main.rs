@@ -1,44 +1,244 @@
 mod cli;
-mod color;
-mod game;
-mod input;
-mod log;
-mod macros;
-mod odd;
-mod positive;
-mod prelude;
-mod voxelbox;
-
-use clap::Parser;
-use game::game_loop;
+
+use cli::GameKind;
 use gilrs::Gilrs;
-use log::Severity;
+use pong::{
+    breakout::Breakout,
+    framework,
+    game::{
+        self, game_loop,
+        level::Level,
+        menu_loop,
+        multiball::MultiballSettings,
+        powerup::PowerUpSettings,
+        simulation::{self, SimulationSettings},
+        state::{SetRules, WinCondition},
+        GameSettings, PlayerSettings,
+    },
+    input::{Controllers, JoyStick, KeyboardLayout, PlayerInput, Server, Terminal},
+    log,
+    snake::Snake,
+    voxelbox::Voxelbox,
+};
+use std::process::ExitCode;
+
+/// Errors after the terminal was opened return from `main` instead of exiting the process,
+/// so that the terminal is restored when it is dropped
+fn main() -> ExitCode {
+    let args = cli::Args::parse_checked();
+    let level = match &args.level {
+        Some(path) => Level::load(path),
+        None => Ok(Level::default()),
+    }
+    .and_then(|level| {
+        level.with_paddle(
+            game::state::Player::Player1,
+            args.paddle_shape_p1.clone(),
+            args.paddle_size_p1,
+        )
+    })
+    .and_then(|level| {
+        level.with_paddle(
+            game::state::Player::Player2,
+            args.paddle_shape_p2.clone(),
+            args.paddle_size_p2,
+        )
+    })
+    .and_then(|level| level.with_players(args.players))
+    .and_then(|level| level.with_teams(args.teams))
+    .and_then(|level| level.with_paddle_depth(args.paddle_depth))
+    .unwrap_or_else(|err| {
+        log!(Critical, "{}", err);
+        std::process::exit(1);
+    });
+    if args.level.is_some() {
+        log!(Log, "Loaded level: {}", level);
+    }
+
+    let win_condition = match (args.lives, args.time_limit) {
+        (Some(lives), _) => WinCondition::Lives(lives),
+        (None, Some(time_limit)) => WinCondition::Time(time_limit),
+        (None, None) => WinCondition::Points(args.winning_points),
+    };
+    let set_rules = SetRules {
+        win_by_two: args.win_by_two,
+        cap: args.point_cap,
+        best_of: args.sets,
+    };
+    let bots = [args.bot_p1, args.bot_p2, args.bot_p3, args.bot_p4];
+    let players = level.active_players().len();
+    // A practice has no score, it ends when the player stops
+    if players == 1 && (args.lives.is_some() || args.time_limit.is_some()) {
+        log!(
+            Critical,
+            "A practice with a single player can't be played with --lives or --time-limit"
+        );
+        return ExitCode::FAILURE;
+    }
+
+    let settings = GameSettings {
+        players: [
+            (args.sensitivity_p1, args.control_mode_p1, args.bot_p1),
+            (args.sensitivity_p2, args.control_mode_p2, args.bot_p2),
+            (args.sensitivity_p3, args.control_mode_p3, args.bot_p3),
+            (args.sensitivity_p4, args.control_mode_p4, args.bot_p4),
+        ]
+        .map(|(sensitivity, control, bot)| PlayerSettings {
+            sensitivity,
+            control,
+            bot,
+        }),
+        connectivity: (args.ip, args.port),
+        win_condition,
+        set_rules,
+        multiball: MultiballSettings {
+            paddle_hits: args.multiball_hits,
+            interval: args.multiball_interval,
+            max_balls: args.max_balls,
+        },
+        powerups: PowerUpSettings {
+            interval: args.powerup_interval,
+            duration: args.powerup_duration,
+        },
+        level,
+    };
 
-fn main() {
-    let args = cli::Args::parse();
+    if let Some(matches) = args.simulate {
+        if players == 1 {
+            log!(Critical, "A simulation needs at least 2 players");
+            return ExitCode::FAILURE;
+        }
+        let settings = SimulationSettings {
+            matches,
+            seed: args.seed.unwrap_or_else(rand::random),
+            bots: bots.map(Option::unwrap_or_default),
+            game: settings,
+        };
+        if args.csv {
+            print!("{}", simulation::csv(&settings));
+        } else {
+            print!("{}", simulation::summary(&settings));
+        }
+        return ExitCode::SUCCESS;
+    }
 
     let mut gilrs = Gilrs::new().expect("Failed to initialize gilrs, needed to get controllers");
-    let mut gamepads = gilrs.gamepads();
-    let gp_id = gamepads
-        .next()
-        .unwrap_or_else(|| {
-            log!(Critical, "Plese connect a gampepad");
+    let gamepads: Vec<_> = gilrs.gamepads().map(|(id, _)| id).collect();
+    let humans = bots[..players].iter().filter(|bot| bot.is_none()).count();
+    let server = args.network.map(|port| {
+        let server = Server::bind(("0.0.0.0", port), humans.max(1)).unwrap_or_else(|err| {
+            log!(Critical, "Could not listen on port {}: {}", port, err);
             std::process::exit(1);
-        })
-        .0;
-    let gp_id_2 = gamepads.next().map(|v| v.0);
-    let log_msg = match gp_id_2 {
-        Some(_) => "Both player have a own gamepad",
-        None => "Both player share one gamepad",
+        });
+        log!(Log, "Waiting for remote controllers on UDP port {}", port);
+        server
+    });
+    let terminal = match args.keyboard.then(Terminal::open).transpose() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            log!(Critical, "Could not read the keyboard: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let controllers = match (&terminal, &server) {
+        (Some(terminal), None) => Controllers::Keyboard(terminal),
+        (None, Some(server)) => Controllers::Network(server),
+        (None, None) => Controllers::Gamepads(&gamepads),
+        (Some(_), Some(_)) => {
+            log!(
+                Critical,
+                "Play either on the keyboard or with remote controllers, not both"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    match args.game {
+        GameKind::Pong => {}
+        GameKind::Snake => {
+            let Some((mut inputs, mut voxelbox)) = single_player(controllers, &settings) else {
+                return ExitCode::FAILURE;
+            };
+            framework::run(&mut Snake::new(), &mut inputs, &mut gilrs, &mut voxelbox);
+            return ExitCode::SUCCESS;
+        }
+        GameKind::Breakout => {
+            let Some((mut inputs, mut voxelbox)) = single_player(controllers, &settings) else {
+                return ExitCode::FAILURE;
+            };
+            let level = match settings.level.with_players(Some(1)) {
+                Ok(level) => level,
+                Err(err) => {
+                    log!(Critical, "{}", err);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let mut breakout = Breakout::new(level, settings.players[0].control);
+            framework::run(&mut breakout, &mut inputs, &mut gilrs, &mut voxelbox);
+            return ExitCode::SUCCESS;
+        }
+    }
+    if args.menu {
+        if controllers.is_empty() {
+            log!(Critical, "Plese connect a gampepad");
+            return ExitCode::FAILURE;
+        }
+        log!(Log, "Pick a mode on the cube");
+        menu_loop(settings, &mut gilrs, controllers);
+        return ExitCode::SUCCESS;
+    }
+    if humans > 0 && controllers.is_empty() {
+        log!(Critical, "Plese connect a gampepad");
+        return ExitCode::FAILURE;
+    }
+    if players > 2 && controllers.len() < humans {
+        match controllers {
+            Controllers::Keyboard(_) => log!(Critical, "The keyboard only has keys for 2 players"),
+            Controllers::Gamepads(_) | Controllers::Network(_) => log!(
+                Critical,
+                "Every one of the {} players needs an own gamepad",
+                humans
+            ),
+        }
+        return ExitCode::FAILURE;
+    }
+    let log_msg = match controllers.len() {
+        _ if args.keyboard => {
+            "Playing on the keyboard with WASD and the arrow keys, quit with Ctrl-C"
+        }
+        _ if players == 1 => "Practicing against the opposite wall",
+        _ if humans == 0 => "The bots play against each other",
+        _ if args.network.is_some() => "Every player joins with a remote controller",
+        _ if humans < players => "Playing against the bot",
+        1 => "Both player share one gamepad",
+        _ if players > 2 => "Every player has an own gamepad",
+        _ => "Both player have a own gamepad",
     };
     log!(Log, "{}", log_msg);
 
-    game_loop(
-        args.sensitivity_p1,
-        args.sensitivity_p2,
-        (args.ip, args.port),
-        &mut gilrs,
-        (gp_id, gp_id_2),
-        args.winning_points,
-    );
+    game_loop(settings, &mut gilrs, controllers);
+    ExitCode::SUCCESS
+}
+
+/// The input of player 1 on the first gamepad, WASD or the first remote controller and the Voxelbox for the single player games,
+/// `None` without a gamepad
+fn single_player(
+    controllers: Controllers,
+    settings: &GameSettings,
+) -> Option<([PlayerInput; 1], Voxelbox)> {
+    let input = match controllers {
+        Controllers::Keyboard(terminal) => {
+            PlayerInput::Keyboard(terminal.keyboard(KeyboardLayout::Wasd, false))
+        }
+        Controllers::Network(server) => PlayerInput::Remote(server.remote(0, false)),
+        Controllers::Gamepads(gamepads) => {
+            let Some(&gamepad) = gamepads.first() else {
+                log!(Critical, "Plese connect a gampepad");
+                return None;
+            };
+            let sensitivity = settings.players[0].sensitivity.clone();
+            PlayerInput::JoyStick(JoyStick::new_player_1(gamepad, sensitivity))
+        }
+    };
+    let (ip, port) = settings.connectivity.clone();
+    Some(([input], Voxelbox::new(ip, port)))
 }
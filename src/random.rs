@@ -0,0 +1,16 @@
+use rand::{rngs::StdRng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_os_rng());
+}
+
+/// Makes every following random decision of this thread reproducible, e.g. to replay a simulation
+pub fn seed(seed: u64) {
+    RNG.with_borrow_mut(|rng| *rng = StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with the random number generator of this thread, it is only seeded by [`seed`]
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with_borrow_mut(f)
+}
@@ -0,0 +1,323 @@
+use crate::{
+    color::Rgb,
+    framework::{Game, Status},
+    input::{Deflection, GameInput, PlayerInput},
+    log, random,
+    voxelbox::{self, Voxelbox, Voxels},
+};
+use rand::seq::IndexedRandom;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
+
+type Voxel = (u8, u8, u8);
+type Direction = (i8, i8, i8);
+
+const START_LENGTH: u8 = 3;
+/// The number of segments the snake grows by every food
+const GROWTH: u8 = 2;
+const START_INTERVAL: Duration = Duration::from_millis(250);
+/// Every food shortens the time between two steps by this, down to `MIN_INTERVAL`
+const SPEEDUP: Duration = Duration::from_millis(5);
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A turn chosen with the stick, the stick has to be moved again for the next turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Turn {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Turn {
+    /// The stick turns the snake when it is moved beyond this value
+    const THRESHOLD: f32 = 0.5;
+
+    /// The turn the stick points to, the axis moved further wins
+    fn of(input: &impl GameInput) -> Option<Self> {
+        let Deflection { x, y } = input.deflection();
+        if x.abs().max(y.abs()) < Self::THRESHOLD {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x < 0.0 { Self::Right } else { Self::Left })
+        } else {
+            Some(if y < 0.0 { Self::Up } else { Self::Down })
+        }
+    }
+
+    /// The new direction of a snake moving towards `heading`.
+    /// Left and right move along the x axis and up and down along the y axis,
+    /// while the snake already moves along that axis they move along the z axis instead
+    const fn direction(self, heading: Direction) -> Direction {
+        let (x, y, _) = heading;
+        match self {
+            Self::Left | Self::Right => {
+                let sign = if matches!(self, Self::Right) { 1 } else { -1 };
+                if x == 0 {
+                    (sign, 0, 0)
+                } else {
+                    (0, 0, sign)
+                }
+            }
+            Self::Up | Self::Down => {
+                let sign = if matches!(self, Self::Down) { 1 } else { -1 };
+                if y == 0 {
+                    (0, sign, 0)
+                } else {
+                    (0, 0, sign)
+                }
+            }
+        }
+    }
+}
+
+/// A snake moving through the whole cube, eating food to grow.
+/// It dies when it hits a wall of the cube or itself and the game ends once it fills the cube,
+/// the first input steers it
+pub struct Snake {
+    /// The voxels of the snake, the head first
+    body: VecDeque<Voxel>,
+    heading: Direction,
+    /// The turn taken with the next step
+    turn: Option<Turn>,
+    /// Where the stick pointed at the last update
+    stick: Option<Turn>,
+    food: Voxel,
+    /// The segments still growing out of the tail
+    growth: u8,
+    eaten: u16,
+    since_step: Duration,
+}
+
+impl Snake {
+    pub fn new() -> Self {
+        let (x, y, z) = (
+            voxelbox::WIDTH / 2,
+            voxelbox::HEIGHT / 2,
+            voxelbox::DEEPTH / 2,
+        );
+        let mut snake = Self {
+            body: (0..START_LENGTH).map(|tail| (x - tail, y, z)).collect(),
+            heading: (1, 0, 0),
+            turn: None,
+            stick: None,
+            food: (0, 0, 0),
+            growth: 0,
+            eaten: 0,
+            since_step: Duration::ZERO,
+        };
+        snake.food = snake.free_voxel().expect("The cube has room for the snake");
+        snake
+    }
+
+    pub fn length(&self) -> usize {
+        self.body.len()
+    }
+
+    pub const fn eaten(&self) -> u16 {
+        self.eaten
+    }
+
+    fn interval(&self) -> Duration {
+        START_INTERVAL
+            .saturating_sub(SPEEDUP * self.eaten.into())
+            .max(MIN_INTERVAL)
+    }
+
+    /// A random voxel of the cube the snake isn't on, `None` once the snake fills the cube
+    fn free_voxel(&self) -> Option<Voxel> {
+        let body: HashSet<_> = self.body.iter().collect();
+        let free: Vec<_> = (0..voxelbox::WIDTH)
+            .flat_map(|x| (0..voxelbox::HEIGHT).map(move |y| (x, y)))
+            .flat_map(|(x, y)| (0..voxelbox::DEEPTH).map(move |z| (x, y, z)))
+            .filter(|voxel| !body.contains(voxel))
+            .collect();
+        random::with_rng(|rng| free.choose(rng).copied())
+    }
+
+    /// Moves the snake by one voxel, returns `Finished` if it died
+    fn step(&mut self) -> Status {
+        if let Some(turn) = self.turn.take() {
+            self.heading = turn.direction(self.heading);
+        }
+        let Some(head) = next(self.body[0], self.heading) else {
+            return self.die("hit the wall");
+        };
+
+        // The tail moves away before the head arrives
+        if self.growth > 0 {
+            self.growth -= 1;
+        } else {
+            self.body.pop_back();
+        }
+        if self.body.contains(&head) {
+            return self.die("bit itself");
+        }
+        self.body.push_front(head);
+
+        if head == self.food {
+            self.eaten += 1;
+            self.growth += GROWTH;
+            let Some(food) = self.free_voxel() else {
+                return self.die("filled the whole cube");
+            };
+            self.food = food;
+        }
+        Status::Running
+    }
+
+    fn die(&self, reason: &str) -> Status {
+        log!(
+            Success,
+            "The snake {} after eating {} food, it was {} voxels long",
+            reason,
+            self.eaten,
+            self.length()
+        );
+        Status::Finished
+    }
+}
+
+impl Default for Snake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The neighbour of `voxel` towards `direction`, if it is inside the cube
+fn next((x, y, z): Voxel, (dx, dy, dz): Direction) -> Option<Voxel> {
+    let axis = |position: u8, direction: i8, length: u8| {
+        position
+            .checked_add_signed(direction)
+            .filter(|&position| position < length)
+    };
+    Some((
+        axis(x, dx, voxelbox::WIDTH)?,
+        axis(y, dy, voxelbox::HEIGHT)?,
+        axis(z, dz, voxelbox::DEEPTH)?,
+    ))
+}
+
+impl Game for Snake {
+    fn update(&mut self, dt: Duration, inputs: &mut [PlayerInput]) -> Status {
+        let stick = inputs.first().and_then(Turn::of);
+        if stick.is_some() && stick != self.stick {
+            self.turn = stick;
+        }
+        self.stick = stick;
+
+        self.since_step += dt;
+        while self.since_step >= self.interval() {
+            self.since_step -= self.interval();
+            if self.step() == Status::Finished {
+                return Status::Finished;
+            }
+        }
+        Status::Running
+    }
+
+    fn render(&self, voxelbox: &mut Voxelbox) {
+        voxelbox.draw(&Voxels {
+            voxels: on_cube([&self.food]),
+            color: Rgb::red(),
+        });
+        voxelbox.draw(&Voxels {
+            voxels: on_cube(self.body.iter().skip(1)),
+            color: Rgb::green(),
+        });
+        voxelbox.draw(&Voxels {
+            voxels: on_cube(self.body.iter().take(1)),
+            color: Rgb::lime(),
+        });
+    }
+}
+
+fn on_cube<'a>(voxels: impl IntoIterator<Item = &'a Voxel>) -> Vec<(usize, usize, usize)> {
+    voxels
+        .into_iter()
+        .map(|&(x, y, z)| (x.into(), y.into(), z.into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn() {
+        // Moving along x, left and right lead along z
+        assert_eq!(Turn::Right.direction((1, 0, 0)), (0, 0, 1));
+        assert_eq!(Turn::Up.direction((1, 0, 0)), (0, -1, 0));
+        // Moving along z, the stick points along x and y
+        assert_eq!(Turn::Left.direction((0, 0, 1)), (-1, 0, 0));
+        assert_eq!(Turn::Down.direction((0, 0, -1)), (0, 1, 0));
+        // Moving along y, up and down lead along z
+        assert_eq!(Turn::Up.direction((0, 1, 0)), (0, 0, -1));
+    }
+
+    #[test]
+    fn test_snake() {
+        let mut snake = Snake::new();
+        assert_eq!(snake.length(), 3);
+        assert!(!snake.body.contains(&snake.food));
+
+        // The snake eats the food in front of it and grows over the next steps
+        snake.food = (11, 10, 6);
+        assert_eq!(snake.update(START_INTERVAL, &mut []), Status::Running);
+        assert_eq!(snake.body[0], (11, 10, 6));
+        assert_eq!(snake.eaten(), 1);
+        assert_ne!(snake.food, (11, 10, 6));
+        snake.food = (0, 0, 0);
+        for _ in 0..GROWTH {
+            snake.step();
+        }
+        assert_eq!(snake.length(), 3 + GROWTH as usize);
+        snake.step();
+        assert_eq!(snake.length(), 3 + GROWTH as usize);
+        assert_eq!(snake.body[0], (14, 10, 6));
+
+        // Turning around on the spot bites the own tail
+        for turn in [Turn::Down, Turn::Left] {
+            snake.turn = Some(turn);
+            assert_eq!(snake.step(), Status::Running);
+        }
+        snake.turn = Some(Turn::Up);
+        assert_eq!(snake.step(), Status::Finished);
+
+        // The walls of the cube end the game
+        let mut snake = Snake::new();
+        snake.food = (0, 0, 0);
+        snake.heading = (0, 0, 1);
+        let steps = voxelbox::DEEPTH - 1 - 6;
+        for _ in 0..steps {
+            assert_eq!(snake.step(), Status::Running);
+        }
+        assert_eq!(snake.step(), Status::Finished);
+    }
+
+    #[test]
+    fn test_full_cube() {
+        let mut snake = Snake::new();
+        let last = (0, 0, 0);
+        snake.body = (0..voxelbox::WIDTH)
+            .flat_map(|x| (0..voxelbox::HEIGHT).map(move |y| (x, y)))
+            .flat_map(|(x, y)| (0..voxelbox::DEEPTH).map(move |z| (x, y, z)))
+            .filter(|&voxel| voxel != last)
+            .collect();
+        assert_eq!(snake.free_voxel(), Some(last));
+
+        // Eating the last food fills the cube and wins the game
+        snake.body.push_front(last);
+        assert_eq!(snake.free_voxel(), None);
+    }
+
+    #[test]
+    fn test_seeded_food() {
+        crate::random::seed(7);
+        let food = Snake::new().food;
+        crate::random::seed(7);
+        assert_eq!(Snake::new().food, food);
+    }
+}
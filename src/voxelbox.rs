@@ -30,6 +30,22 @@ pub trait Draw {
     fn draw(&self) -> Vec<(usize, usize, usize)>;
 }
 
+/// Voxels of a single color, for things on the cube without an own type
+pub struct Voxels {
+    pub voxels: Vec<(usize, usize, usize)>,
+    pub color: Rgb,
+}
+
+impl Draw for Voxels {
+    fn color(&self) -> Rgb {
+        self.color
+    }
+
+    fn draw(&self) -> Vec<(usize, usize, usize)> {
+        self.voxels.clone()
+    }
+}
+
 #[derive(Debug)]
 pub enum VoxelBoxSendError {
     BindError,